@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+use clap::Parser;
+use encoding_rs::Encoding;
+use crate::editor::{GotoTarget, LineEnding};
+use crate::error;
+
+/// 内置的欢迎屏幕文本.
+pub const WELCOME_TEXT: &str = include_str!("../welcome.txt");
+
+// 字段的文档注释会作为 `--help` 的输出, 所以使用英文.
+/// A light-weight terminal text editor.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// File to edit, `FILE:LINE[:COLUMN]` opens the file at the position, `-` reads the text from stdin.
+    /// Without FILE the text is read from stdin when stdin is not a terminal.
+    /// A preceding `+LINE` places the caret at LINE, a single `+` at the last line.
+    #[arg(value_name = "[+LINE] FILE")]
+    pub args: Vec<String>,
+    /// Write the text to FILE when saving, instead of the opened file.
+    /// Useful when editing text read from stdin, e.g. `vegetor -o out.txt < in.txt`.
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+    /// Open the text in read-only mode.
+    #[arg(short = 'R', long)]
+    pub readonly: bool,
+    /// Start editing directly without showing the welcome screen.
+    #[arg(long)]
+    pub no_welcome: bool,
+    /// Directory of the configuration files `config.toml` and `keys.toml`,
+    /// defaults to `$XDG_CONFIG_HOME/vegetor` or `~/.config/vegetor`.
+    #[arg(long, value_name = "DIR")]
+    pub config: Option<PathBuf>,
+    /// Encoding used to read and write the file, e.g. `utf-8`, `gbk`, `utf-16le`. A BOM in the file takes precedence.
+    #[arg(long, value_parser = parse_encoding)]
+    pub encoding: Option<&'static Encoding>,
+    /// Line ending used when saving the file, detected from the file by default.
+    #[arg(long, value_name = "lf|crlf", value_parser = parse_line_ending)]
+    pub line_ending: Option<LineEnding>,
+}
+
+impl Cli {
+    /// 配置文件所在的目录, 没有通过 `--config` 指定时为 [`crate::config_dir`].
+    pub fn config_dir(&self) -> Option<PathBuf> {
+        self.config.clone().or_else(crate::config_dir)
+    }
+
+    /// 从位置参数中分离出要编辑的文件和 caret 的初始位置.
+    ///
+    /// # Errors
+    ///
+    /// 指定了多于一个文件时返回错误信息.
+    pub fn file_and_position(&self) -> Result<(Option<&str>, Option<GotoTarget>), String> {
+        let mut file = None;
+        let mut position = None;
+        for arg in &self.args {
+            match parse_line_argument(arg) {
+                Some(target) => position = Some(target),
+                None if file.is_some() => return Err(format!("unexpected argument `{arg}`, only one file can be edited")),
+                None => file = Some(arg.as_str()),
+            }
+        }
+        let Some(file) = file else {
+            return Ok((None, position));
+        };
+        let (file, file_position) = if file == "-" { (file, None) } else { split_file_position(file) };
+        Ok((Some(file), file_position.or(position)))
+    }
+}
+
+/// 通过名称查找编码, 名称见 [WHATWG Encoding Standard](https://encoding.spec.whatwg.org/#names-and-labels).
+fn parse_encoding(label: &str) -> error::Result<&'static Encoding> {
+    Encoding::for_label(label.as_bytes()).ok_or_else(|| error::Error::UnknownEncoding(label.to_owned()))
+}
+
+fn parse_line_ending(s: &str) -> error::Result<LineEnding> {
+    s.parse()
+}
+
+/// 从命令行中的文件参数中分离出文件路径和位置, 支持 `path:line` 和 `path:line:column` 两种格式,
+/// 末尾可以带有一个 `:`, 比如编译器输出的 `src/lib.rs:12:5:`.
+///
+/// 如果参数本身是一个存在的文件路径, 那么不进行分离, 以支持文件名中带有 `:` 的文件.
+pub fn split_file_position(arg: &str) -> (&str, Option<GotoTarget>) {
+    if Path::new(arg).exists() {
+        return (arg, None);
+    }
+    let trimmed = arg.strip_suffix(':').unwrap_or(arg);
+    let mut parts = trimmed.rsplitn(3, ':');
+    let (last, middle, first) = (parts.next(), parts.next(), parts.next());
+    let number = |s: Option<&str>| s.and_then(|s| s.parse::<usize>().ok()).filter(|n| *n > 0);
+    if let (Some(line), Some(column), Some(path)) = (number(middle), number(last), first) {
+        if !path.is_empty() {
+            return (path, Some(GotoTarget::Line(line, Some(column))));
+        }
+    }
+    if let Some((path, line)) = trimmed.rsplit_once(':') {
+        if let (Some(line), false) = (number(Some(line)), path.is_empty()) {
+            return (path, Some(GotoTarget::Line(line, None)));
+        }
+    }
+    (arg, None)
+}
+
+/// 解析 `+N` 格式的行号参数, 单独的 `+` 表示最后一行.
+///
+/// # Returns
+///
+/// 参数不是行号参数时返回 None.
+pub fn parse_line_argument(arg: &str) -> Option<GotoTarget> {
+    let line = arg.strip_prefix('+')?;
+    if line.is_empty() {
+        return Some(GotoTarget::Percent(100));
+    }
+    line.parse().ok().filter(|line| *line > 0).map(|line| GotoTarget::Line(line, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use crate::cli::{parse_line_argument, split_file_position, Cli};
+    use crate::editor::{GotoTarget, LineEnding};
+
+    #[test]
+    fn parse_cli() {
+        let cli = Cli::try_parse_from(["vegetor", "-R", "--encoding", "GBK", "--line-ending", "crlf", "+3", "no/such/file.rs"]).unwrap();
+        assert!(cli.readonly);
+        assert_eq!(cli.encoding, Some(encoding_rs::GBK));
+        assert_eq!(cli.line_ending, Some(LineEnding::CrLf));
+        assert_eq!(cli.file_and_position().unwrap(), (Some("no/such/file.rs"), Some(GotoTarget::Line(3, None))));
+
+        let cli = Cli::try_parse_from(["vegetor", "+3", "no/such/file.rs:5"]).unwrap();
+        assert_eq!(cli.file_and_position().unwrap(), (Some("no/such/file.rs"), Some(GotoTarget::Line(5, None))));
+        let cli = Cli::try_parse_from(["vegetor", "-", "+"]).unwrap();
+        assert_eq!(cli.file_and_position().unwrap(), (Some("-"), Some(GotoTarget::Percent(100))));
+        let cli = Cli::try_parse_from(["vegetor", "-o", "out.txt"]).unwrap();
+        assert_eq!(cli.output.as_deref(), Some(std::path::Path::new("out.txt")));
+        assert_eq!(cli.file_and_position().unwrap(), (None, None));
+        let cli = Cli::try_parse_from(["vegetor", "a.txt", "b.txt"]).unwrap();
+        assert!(cli.file_and_position().is_err());
+
+        assert!(Cli::try_parse_from(["vegetor", "--encoding", "no-such-encoding"]).is_err());
+        assert!(Cli::try_parse_from(["vegetor", "--line-ending", "cr"]).is_err());
+    }
+
+    #[test]
+    fn file_position() {
+        assert_eq!(
+            split_file_position("no/such/file.rs:12:5"),
+            ("no/such/file.rs", Some(GotoTarget::Line(12, Some(5))))
+        );
+        assert_eq!(
+            split_file_position("no/such/file.rs:12:5:"),
+            ("no/such/file.rs", Some(GotoTarget::Line(12, Some(5))))
+        );
+        assert_eq!(split_file_position("no/such/file.rs:12"), ("no/such/file.rs", Some(GotoTarget::Line(12, None))));
+        assert_eq!(split_file_position("no/such/file.rs"), ("no/such/file.rs", None));
+        assert_eq!(split_file_position("no/such/file.rs:0"), ("no/such/file.rs:0", None));
+        assert_eq!(split_file_position("a:b:3"), ("a:b", Some(GotoTarget::Line(3, None))));
+        assert_eq!(split_file_position(":12"), (":12", None));
+        assert_eq!(split_file_position("Cargo.toml"), ("Cargo.toml", None));
+    }
+
+    #[test]
+    fn line_argument() {
+        assert_eq!(parse_line_argument("+40"), Some(GotoTarget::Line(40, None)));
+        assert_eq!(parse_line_argument("+"), Some(GotoTarget::Percent(100)));
+        assert_eq!(parse_line_argument("+0"), None);
+        assert_eq!(parse_line_argument("40"), None);
+        assert_eq!(parse_line_argument("+x"), None);
+    }
+}
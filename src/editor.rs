@@ -1,22 +1,51 @@
 use std::fmt::Write;
-use std::path;
+use std::time::{Duration, Instant};
+use std::{io, path};
 
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 
-pub use crate::editor::terminal::{Location, Size};
+pub use crate::editor::terminal::{Color, EventSource, Location, RenderTarget, Size, Style, Terminal};
+pub use crate::editor::virtualterminal::{Cell, VirtualTerminal};
+pub use crate::editor::keymap::KeymapPreset;
+pub use crate::editor::editarea::GotoTarget;
+pub use crate::editor::buffer::LineEnding;
+pub use crate::editor::message::Severity;
+use encoding_rs::Encoding;
+use crate::editor::command::Command;
 use crate::editor::editarea::{Area, EditArea};
-use crate::editor::terminal::Terminal;
+use crate::editor::keymap::{KeyChord, KeyLookup, Keymap};
+use crate::editor::killring::KillRing;
+use crate::editor::message::Messages;
+use crate::editor::prompt::{Prompt, PromptKind, PromptResult};
 use crate::error;
 use crate::CARGO_PKG_NAME;
-use crate::editor::statusbar::{Packing, StatusBar};
+use crate::editor::statusbar::{Packing, Segment, StatusBar};
+use crate::editor::filetype::Filetype;
+use crate::editor::settings::{Settings, SettingsConfig, SettingsLayer};
+use crate::editor::editorconfig::EditorConfig;
+use crate::editor::buffer::Buffer;
 
 mod editarea;
 mod terminal;
 mod buffer;
 mod statusbar;
+mod command;
+mod keymap;
+mod killring;
+mod prompt;
+mod filetype;
+mod settings;
+mod editorconfig;
+mod word;
+mod message;
+mod virtualterminal;
+#[cfg(test)]
+mod replay;
 
-/// tab 键插入的空格数量.
-const TAB_WIDTH: usize = 4;
+/// 连续点击被视为双击或三击的最大间隔.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+/// 鼠标滚轮每次滚动的行数.
+const MOUSE_SCROLL_LINES: isize = 3;
 
 trait Printable {
     /// 此对象是否需要重绘.
@@ -31,15 +60,30 @@ trait Printable {
 pub enum State {
     Welcoming,
     Editing,
+    /// 查看消息历史, 见 [`Command::Messages`].
+    Messages,
     Exiting,
 }
 
-#[derive(Debug)]
+/// 会关闭当前 buffer 的操作, buffer 有未保存的修改时需要先确认, 见 [`Editor::close_buffer`].
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum CloseAction {
+    /// 退出编辑器.
+    Quit,
+    /// 打开另一个文件替换当前 buffer.
+    Open(String),
+}
+
+#[derive(Debug, Default)]
 pub enum BufferLoadConfig<'a> {
     /// 使用文件的内容来填充 buffer.
     File(&'a path::Path),
     /// 使用字符串的内容来填充 buffer.
     String(&'a str),
+    /// 读取标准输入直到 EOF, 使用读取到的内容来填充 buffer, 比如 `git log | vegetor -`.
+    ///
+    /// 读取完毕之后编辑器从 `/dev/tty` 读取按键, 见 [`Terminal::read_event_blocking`].
+    Stdin,
     /// buffer 为空.
     ///
     /// # Notice
@@ -48,29 +92,91 @@ pub enum BufferLoadConfig<'a> {
     ///
     /// 在初始化 welcome_buffer 时, 如果给了 [`BufferLoadConfig::Empty`], 那么不会显示 welcome 屏幕,
     /// 而是直接进入编辑状态.
+    #[default]
     Empty,
 }
 
-impl<'a> Default for BufferLoadConfig<'a> {
-    fn default() -> BufferLoadConfig<'a> {
-        BufferLoadConfig::Empty
-    }
-}
-
 #[derive(Debug, Default)]
 pub struct EditorBuildConfig<'a> {
     /// 设置欢迎屏幕的显示内容.
     ///
     /// - [`BufferLoadConfig::File`]: 此选项会加载指定的文件, 然后对文件内容在欢迎屏幕上居中显示.
-    /// - [`BufferLoadConfig::String`]: 此选项会让欢迎屏幕居中显示指定字符串.
+    /// - [`BufferLoadConfig::String`]: 此选项会使用指定的字符串, 然后在欢迎屏幕上居中显示.
+    /// - [`BufferLoadConfig::Stdin`]: 此选项会使用标准输入的内容, 然后在欢迎屏幕上居中显示.
     /// - [`BufferLoadConfig::Empty`]: 此选项会让 [`Editor`] 直接跳过欢迎阶段, 直接进入编辑阶段.
     pub welcome_config: BufferLoadConfig<'a>,
     /// 设置要进行编辑的文本.
     ///
     /// - [`BufferLoadConfig::File`]: 此选项会加载指定的文件, 然后对文件内容进行编辑.
     /// - [`BufferLoadConfig::String`]: 此选项会初始化 buffer 为指定的字符串, 并对其进行编辑.
+    /// - [`BufferLoadConfig::Stdin`]: 此选项会初始化 buffer 为标准输入的内容, 并对其进行编辑.
     /// - [`BufferLoadConfig::Empty`]: 此选项让 buffer 初始化为空.
     pub edit_text_config: BufferLoadConfig<'a>,
+    /// 保存时写入的文件, 代替 `edit_text_config` 中的文件, 比如把标准输入的内容编辑之后保存到文件中.
+    pub output_path: Option<&'a path::Path>,
+    /// 加载要编辑的文本之后 caret 的初始位置, 超出文本范围的位置会被限制到文本范围内.
+    ///
+    /// 为 None 时 caret 在文本末尾.
+    pub caret_position: Option<GotoTarget>,
+    /// 设置使用的按键绑定预设, 见 [`KeymapPreset`].
+    pub keymap_preset: KeymapPreset,
+    /// 按键绑定配置文件 (`keys.toml`), 为 None 时直接使用 `keymap_preset`.
+    ///
+    /// 配置文件中没有指定预设时, 在 `keymap_preset` 的基础上修改按键绑定.
+    pub keymap_config: Option<&'a path::Path>,
+    /// 设置的配置文件 (`config.toml`), 格式见 [`SettingsConfig`], 为 None 时使用默认设置.
+    pub settings_config: Option<&'a path::Path>,
+    /// 只读模式, 不允许修改文本.
+    pub readonly: bool,
+    /// 加载和保存文件时使用的编码, 为 None 时使用 UTF-8, 文件开头的 BOM 优先于此设置.
+    pub encoding: Option<&'static Encoding>,
+    /// 保存文件时使用的换行符, 为 None 时根据文件内容判断.
+    pub line_ending: Option<LineEnding>,
+}
+
+/// 命令的重复次数 (universal argument) 的输入状态.
+#[derive(Debug, Default)]
+struct UniversalArgument {
+    /// 当前的重复次数, None 表示没有输入 universal argument.
+    count: Option<usize>,
+    /// 是否已经输入了数字, 输入数字之后再次输入 universal argument 会结束数字的输入.
+    has_digits: bool,
+    /// 是否还在接收数字输入.
+    reading_digits: bool,
+}
+
+impl UniversalArgument {
+    /// 处理 [`Command::UniversalArgument`].
+    fn start(&mut self) {
+        match self.count {
+            None => self.count = Some(4),
+            Some(count) if !self.has_digits => self.count = Some(count.saturating_mul(4)),
+            Some(_) => {}
+        }
+        self.reading_digits = !self.has_digits;
+    }
+
+    /// 尝试把字符作为 universal argument 的数字输入.
+    ///
+    /// # Returns
+    ///
+    /// 字符是否被作为数字接收.
+    fn push_digit(&mut self, ch: char) -> bool {
+        let Some(digit) = ch.to_digit(10).filter(|_| self.reading_digits) else {
+            return false;
+        };
+        let count = if self.has_digits { self.count.unwrap_or(0) } else { 0 };
+        self.count = Some(count.saturating_mul(10).saturating_add(digit as usize));
+        self.has_digits = true;
+        true
+    }
+
+    /// 取出重复次数, 并重置状态, 没有输入 universal argument 时为 1.
+    fn take(&mut self) -> usize {
+        let count = self.count.unwrap_or(1);
+        *self = UniversalArgument::default();
+        count
+    }
 }
 
 pub struct Editor {
@@ -78,6 +184,35 @@ pub struct Editor {
     status_bar: StatusBar,
     terminal: Terminal,
     state: State,
+    keymap: Keymap,
+    /// 已经按下但是还没有匹配到命令的按键序列 (比如 Emacs 中的 `C-x`).
+    pending_keys: Vec<KeyChord>,
+    universal_argument: UniversalArgument,
+    kill_ring: KillRing,
+    /// 上一条执行的命令, 用于合并连续的 kill 操作.
+    last_command: Option<Command>,
+    /// 正在状态栏中进行的输入, 存在时按键都交给它处理.
+    prompt: Option<Prompt>,
+    /// 配置文件中的设置.
+    settings_config: SettingsConfig,
+    /// 运行时通过 [`Command::Set`] 修改的设置.
+    runtime_settings: SettingsLayer,
+    /// 当前生效的设置, 见 [`Editor::apply_settings`].
+    settings: Settings,
+    /// 正在编辑的文件的类型, 见 [`Filetype::detect`].
+    filetype: Option<&'static Filetype>,
+    /// 作用于正在编辑的文件的 `.editorconfig`.
+    editorconfig: EditorConfig,
+    /// 只读模式, 见 [`EditorBuildConfig::readonly`].
+    readonly: bool,
+    /// 加载文件时使用的编码, 见 [`EditorBuildConfig::encoding`].
+    encoding: Option<&'static Encoding>,
+    /// 上一次鼠标点击的时间, 位置和连续点击的次数, 用于识别双击和三击.
+    last_click: Option<(Instant, Location, usize)>,
+    /// 在状态栏中显示的消息及其历史.
+    messages: Messages,
+    /// 等待询问是否保存修改的回答的关闭操作.
+    pending_close: Option<CloseAction>,
 }
 
 impl Editor {
@@ -90,135 +225,212 @@ impl Editor {
         // 如果在 drop 中对 terminal 进行资源清理操作会导致 panic 信息无法显示.
     }
 
+    /// 构建使用真实终端的编辑器, 配置和文本的加载错误会在接管终端之前返回.
     pub fn build(config: &EditorBuildConfig) -> error::Result<Editor> {
+        let editor = Editor::build_on(config, Terminal::new())?;
         let raw_hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(move |info| {
             Editor::panic_handler(info);
             raw_hook(info);
         }));
+        Ok(editor)
+    }
 
-        let mut terminal = Terminal::new();
-        terminal.initialize()?;
-        let mut edit_area = EditArea::new();
-
-        let mut status_bar = StatusBar::new();
-        status_bar.set_content("Hello World".into());
-        status_bar.set_packing(Packing::Left(statusbar::HORIZONTAL_PADDING, statusbar::HORIZONTAL_PADDING));
-
-        let mut editor = Editor {
-            edit_area,
-            status_bar,
-            terminal,
-            state: State::Welcoming,
+    /// 构建在指定终端上运行的编辑器, 比如使用 [`VirtualTerminal`] 在测试中运行或者嵌入到其他界面中,
+    /// 配置和文本的加载错误会在初始化终端之前返回.
+    pub fn build_on(config: &EditorBuildConfig, mut terminal: Terminal) -> error::Result<Editor> {
+        let keymap = match config.keymap_config {
+            Some(path) => Keymap::load(path, config.keymap_preset)?,
+            None => Keymap::from_preset(config.keymap_preset),
+        };
+        let settings_config = match config.settings_config {
+            Some(path) => SettingsConfig::load(path)?,
+            None => SettingsConfig::default(),
         };
+        let mut edit_area = EditArea::new();
+        let mut state = State::Welcoming;
 
         match config.welcome_config {
-            BufferLoadConfig::Empty => { editor.state = State::Editing }
+            BufferLoadConfig::Empty => { state = State::Editing }
             BufferLoadConfig::File(file_path) => {
-                let welcome = editor.edit_area.get_welcome_buffer_mut();
+                let welcome = edit_area.get_welcome_buffer_mut();
                 welcome.load(file_path)?
             }
             BufferLoadConfig::String(string) => {
-                let welcome = editor.edit_area.get_welcome_buffer_mut();
+                let welcome = edit_area.get_welcome_buffer_mut();
                 // welcome.clear(); // 本来就没写什么
                 write!(welcome, "{}", string).unwrap();
             }
+            BufferLoadConfig::Stdin => {
+                let welcome = edit_area.get_welcome_buffer_mut();
+                welcome.load_from_reader(io::stdin())?;
+            }
         }
 
+        if let Some(encoding) = config.encoding {
+            edit_area.get_buffer_mut().set_encoding(encoding);
+        }
+        let mut editorconfig = EditorConfig::default();
         match config.edit_text_config {
             BufferLoadConfig::Empty => {}
             BufferLoadConfig::String(string) => {
-                let buffer = editor.edit_area.get_buffer_mut();
+                let buffer = edit_area.get_buffer_mut();
                 // buffer.clear(); // 本来就没写什么
                 write!(buffer, "{}", string).unwrap();
             }
             BufferLoadConfig::File(path) => {
-                let buffer = editor.edit_area.get_buffer_mut();
-                buffer.load(path)?;
+                let (buffer, file_config) = Editor::load_file(path, config.encoding)?;
+                edit_area.set_buffer(buffer);
+                editorconfig = file_config;
+            }
+            BufferLoadConfig::Stdin => {
+                let buffer = edit_area.get_buffer_mut();
+                buffer.load_from_reader(io::stdin())?;
             }
         }
+        if let Some(path) = config.output_path {
+            edit_area.get_buffer_mut().set_path(path);
+        }
+        if let Some(line_ending) = config.line_ending {
+            edit_area.get_buffer_mut().set_line_ending(line_ending);
+        }
+
+        terminal.initialize().map_err(error::Error::TerminalUnavailable)?;
+
+        let status_bar = StatusBar::new();
 
+        let mut editor = Editor {
+            edit_area,
+            status_bar,
+            terminal,
+            state,
+            keymap,
+            pending_keys: Vec::new(),
+            universal_argument: UniversalArgument::default(),
+            kill_ring: KillRing::new(),
+            last_command: None,
+            prompt: None,
+            settings_config,
+            runtime_settings: SettingsLayer::default(),
+            settings: Settings::default(),
+            filetype: None,
+            editorconfig,
+            readonly: config.readonly,
+            encoding: config.encoding,
+            last_click: None,
+            messages: Messages::new(),
+            pending_close: None,
+        };
+
+        editor.apply_settings();
         editor.update_area_configuration()?;
+        // 在显示区域确定之后再移动 caret, 让显示偏移按照实际的显示区域计算.
+        if let Some(target) = config.caret_position {
+            editor.edit_area.goto_clamped(target);
+        }
 
         Ok(editor)
     }
 
+    /// 运行编辑器直到退出.
+    ///
+    /// 只有终端无法绘制或者无法读取事件时返回错误, 处理事件时的错误在状态栏中显示.
     pub fn run(&mut self) -> error::Result<()> {
         while self.state != State::Exiting {
-            if self.check_need_printing() {
-                self.terminal.clear_screen()?;
-                match self.state {
-                    State::Welcoming => {
-                        self.edit_area.print_welcome_to(&mut self.terminal).or_else(|e| {
-                            match e {
-                                // 忽略 buffer 尺寸不合适的情况.
-                                error::Error::BufferSizeExceeds { .. } => { Ok(()) }
-                                _ => { Err(e) }
-                            }
-                        })?;
-                    }
-                    State::Editing => {
-                        self.status_bar.print_to(&mut self.terminal)?; // 先打印, 因为其无法回归 cursor 位置.
-                        self.edit_area.print_to(&mut self.terminal)?;
-                    }
-                    _ => {}
+            self.render()?;
+            // 有消息正在显示时, 最多等待到消息超时, 然后重新绘制状态栏.
+            if let Some(deadline) = self.messages.current(Instant::now()).map(|message| message.deadline()) {
+                if !self.terminal.poll_event(deadline.saturating_duration_since(Instant::now()))? {
+                    continue;
                 }
-                self.edit_area.unset_need_printing();
-                self.status_bar.unset_need_printing();
             }
-            self.terminal.flush()?;
-            self.handle_event()?;
+            let event = self.terminal.read_event_blocking()?;
+            self.dispatch_event(event);
         }
         Ok(())
     }
 
-    fn handle_event(&mut self) -> error::Result<()> {
-        let evt = self.terminal.read_event_blocking();
-        match evt {
-            Ok(Event::Key(key_event)) => {
-                let KeyEvent { kind, code, modifiers, .. } = key_event;
-                if kind == KeyEventKind::Press {
-                    match code {
-                        KeyCode::Char('q') if modifiers == KeyModifiers::CONTROL => {
-                            self.state = State::Exiting;
+    /// 处理事件来源中已经到达的全部事件, 然后绘制, 不会等待新的事件, 用于在测试中或者嵌入其他界面时驱动编辑器.
+    ///
+    /// 编辑器退出之后不再处理事件, 见 [`Editor::is_exiting`].
+    pub fn run_pending(&mut self) -> error::Result<()> {
+        while self.state != State::Exiting && self.terminal.poll_event(Duration::ZERO)? {
+            let event = self.terminal.read_event_blocking()?;
+            self.dispatch_event(event);
+        }
+        self.render()
+    }
+
+    /// 编辑器是否已经退出, 比如执行了 [`Command::Quit`].
+    pub fn is_exiting(&self) -> bool {
+        self.state == State::Exiting
+    }
+
+    /// 正在编辑的 buffer.
+    pub fn buffer(&self) -> &Buffer {
+        self.edit_area.get_buffer()
+    }
+
+    /// 重新绘制需要绘制的部分.
+    fn render(&mut self) -> error::Result<()> {
+        self.update_status_bar();
+        if self.check_need_printing() {
+            self.terminal.clear_screen()?;
+            match self.state {
+                State::Welcoming => {
+                    self.edit_area.print_welcome_to(&mut self.terminal).or_else(|e| {
+                        match e {
+                            // 忽略 buffer 尺寸不合适的情况.
+                            error::Error::BufferSizeExceeds { .. } => { Ok(()) }
+                            _ => { Err(e) }
                         }
-                        #[cfg(debug_assertions)]
-                        KeyCode::Char('c') if modifiers == KeyModifiers::CONTROL => {
-                            panic!("Ctrl-C");
+                    })?;
+                }
+                State::Messages => {
+                    self.status_bar.print_to(&mut self.terminal)?;
+                    let area = self.edit_area.display_area();
+                    self.messages.print_history_to(&mut self.terminal, area, self.settings.theme)?;
+                }
+                State::Editing => {
+                    match &self.prompt {
+                        Some(prompt) => {
+                            // 输入提示需要 cursor 停留在状态栏中, 所以最后打印.
+                            self.edit_area.print_to(&mut self.terminal)?;
+                            self.status_bar.print_prompt_to(&mut self.terminal, prompt)?;
                         }
-                        _ => {
-                            if self.state == State::Welcoming {
-                                self.state = State::Editing; // 有按键按下就进入 Editing, 其余不做任何动作.
-                                self.edit_area.set_need_printing();
-                            } else if let Ok(caret_move) = key_event.try_into() {
-                                self.terminal.move_cursor_to(self.edit_area.move_caret(caret_move))?;
-                            } else {
-                                match code {
-                                    KeyCode::Char(ch) if modifiers == KeyModifiers::NONE => {
-                                        write!(self.edit_area, "{ch}").unwrap();
-                                    }
-                                    KeyCode::Char('s') if modifiers == KeyModifiers::CONTROL => {
-                                        // todo 当启动时指定文件名了, 那就保存到指定的文件.
-                                        // todo 判断是否有编辑痕迹, 如果有编辑痕迹.
-                                        // todo 如果启动时没有启动参数指定文件名, 那么使用 control s 保存的时候先询问文件名.
-                                    }
-                                    KeyCode::Enter if modifiers == KeyModifiers::NONE => {
-                                        write!(self.edit_area, "\n").unwrap();
-                                    }
-                                    KeyCode::Tab if modifiers == KeyModifiers::NONE => {
-                                        write!(self.edit_area, "{}", " ".repeat(TAB_WIDTH)).unwrap();
-                                    }
-                                    KeyCode::Backspace if modifiers == KeyModifiers::NONE => {
-                                        let _ = self.edit_area.del_char();
-                                    }
-                                    _ => {}
-                                }
-                            }
+                        None => {
+                            self.status_bar.print_to(&mut self.terminal)?; // 先打印, 因为其无法回归 cursor 位置.
+                            self.edit_area.print_to(&mut self.terminal)?;
                         }
                     }
                 }
+                _ => {}
+            }
+            self.edit_area.unset_need_printing();
+            self.status_bar.unset_need_printing();
+        }
+        self.terminal.flush()?;
+        Ok(())
+    }
+
+    /// 处理事件, 处理时的错误不退出编辑器, 而是在状态栏中显示.
+    fn dispatch_event(&mut self, event: Event) {
+        if let Err(e) = self.handle_event(event) {
+            self.messages.push_error(&e);
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> error::Result<()> {
+        match event {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                #[cfg(debug_assertions)]
+                if key_event.code == KeyCode::Char('c') && key_event.modifiers == KeyModifiers::CONTROL {
+                    panic!("Ctrl-C");
+                }
+                self.handle_key(KeyChord::from(key_event))?;
             }
-            Ok(Event::Resize(_, _)) => {
+            Event::Mouse(mouse_event) => self.handle_mouse(mouse_event),
+            Event::Resize(_, _) => {
                 self.update_area_configuration()?;
             }
             _ => {}
@@ -226,6 +438,538 @@ impl Editor {
         Ok(())
     }
 
+    /// 处理鼠标事件: 左键点击移动 caret, 双击选中单词, 三击选中行, 拖动选择, 滚轮滚动显示区域.
+    ///
+    /// 只在编辑状态并且没有输入提示时处理.
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        if self.state != State::Editing || self.prompt.is_some() {
+            return;
+        }
+        let position = Location::new(event.column as usize, event.row as usize);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let now = Instant::now();
+                let clicks = match self.last_click {
+                    Some((time, last, clicks)) if last == position && now - time <= MULTI_CLICK_INTERVAL => clicks % 3 + 1,
+                    _ => 1,
+                };
+                self.last_click = Some((now, position, clicks));
+                self.edit_area.clear_cursors();
+                self.edit_area.cancel_block();
+                self.edit_area.click(position, clicks);
+            }
+            MouseEventKind::Drag(MouseButton::Left) => self.edit_area.drag_to(position),
+            MouseEventKind::ScrollUp => self.edit_area.scroll(-MOUSE_SCROLL_LINES),
+            MouseEventKind::ScrollDown => self.edit_area.scroll(MOUSE_SCROLL_LINES),
+            _ => {}
+        }
+    }
+
+    /// 把按键交给 keymap 匹配, 匹配到命令则执行.
+    fn handle_key(&mut self, key: KeyChord) -> error::Result<()> {
+        if self.prompt.is_some() {
+            return self.handle_prompt_key(key);
+        }
+        if self.state == State::Messages {
+            // 查看消息历史时按下任意键返回编辑.
+            self.enter_editing();
+            return Ok(());
+        }
+        if self.pending_keys.is_empty() {
+            if let Some(ch) = key.self_insert_char() {
+                if self.universal_argument.push_digit(ch) {
+                    return Ok(());
+                }
+            }
+        }
+        self.pending_keys.push(key);
+        match self.keymap.lookup(&self.pending_keys) {
+            KeyLookup::Prefix => return Ok(()),
+            KeyLookup::Command(command) => {
+                self.pending_keys.clear();
+                if self.state == State::Welcoming && command != Command::Quit {
+                    self.enter_editing();
+                } else {
+                    self.execute(command, None)?;
+                }
+            }
+            KeyLookup::Unbound => {
+                let keys: Vec<KeyChord> = self.pending_keys.drain(..).collect();
+                if self.state == State::Welcoming {
+                    self.enter_editing();
+                } else if let [key] = keys[..] {
+                    if let Some(ch) = key.self_insert_char() {
+                        let count = self.universal_argument.take();
+                        if self.readonly {
+                            return Err(error::Error::ReadOnly);
+                        }
+                        if self.edit_area.has_block() {
+                            for _ in 0..count {
+                                self.edit_area.block_insert(ch);
+                            }
+                        } else {
+                            self.edit_area.insert_at_cursors(|edit_area| {
+                                for _ in 0..count {
+                                    edit_area.insert_char(ch);
+                                }
+                            });
+                        }
+                        self.last_command = None;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 有按键按下就从欢迎屏幕进入 Editing, 其余不做任何动作.
+    fn enter_editing(&mut self) {
+        self.state = State::Editing;
+        self.universal_argument = UniversalArgument::default();
+        self.edit_area.set_need_printing();
+    }
+
+    /// 打开输入提示.
+    fn open_prompt(&mut self, prompt: Prompt) {
+        self.prompt = Some(prompt);
+        self.status_bar.set_need_printing();
+    }
+
+    /// 关闭输入提示.
+    fn close_prompt(&mut self) -> Option<Prompt> {
+        self.status_bar.set_need_printing();
+        self.edit_area.set_need_printing();
+        self.prompt.take()
+    }
+
+    /// 打开命令面板, 并预先填入内容.
+    fn open_command_palette(&mut self, input: &str) {
+        self.open_prompt(Prompt::new(PromptKind::Command, ":").with_input(input));
+    }
+
+    /// 把按键交给输入提示处理, 输入提交后根据提示的类型执行相应的操作.
+    fn handle_prompt_key(&mut self, key: KeyChord) -> error::Result<()> {
+        let prompt = self.prompt.as_mut().unwrap();
+        let kind = prompt.kind();
+        let result = prompt.handle_key(key, |input| match kind {
+            PromptKind::Command => command::complete_command_line(input, settings::OPTIONS),
+            PromptKind::Goto | PromptKind::UnsavedChanges => Vec::new(),
+        });
+        self.status_bar.set_need_printing();
+        match result {
+            PromptResult::Pending => {}
+            PromptResult::Cancel => {
+                self.close_prompt();
+                self.pending_close = None;
+            }
+            PromptResult::Submit(input) => {
+                let prompt = self.close_prompt().unwrap();
+                let rst = match prompt.kind() {
+                    PromptKind::Command => {
+                        // 关闭 buffer 之前询问保存的文件名, 见 [`Editor::answer_unsaved_changes`].
+                        let pending_close = self.pending_close.take();
+                        let rst = self.execute_command_line(&input);
+                        match pending_close {
+                            Some(action) if rst.is_ok() => self.close_after_save(action),
+                            _ => rst,
+                        }
+                    }
+                    PromptKind::Goto => self.execute(Command::Goto, Some(&input)),
+                    PromptKind::UnsavedChanges => self.answer_unsaved_changes(&input),
+                };
+                rst?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 关闭当前 buffer, 然后执行 action.
+    ///
+    /// buffer 有未保存的修改并且 force 为 false 时不会关闭, 而是在状态栏中询问是保存, 丢弃修改还是取消,
+    /// 回答见 [`Editor::answer_unsaved_changes`].
+    fn close_buffer(&mut self, action: CloseAction, force: bool) -> error::Result<()> {
+        let buffer = self.edit_area.get_buffer();
+        if !force && buffer.is_modified() {
+            let name = buffer.path().map_or_else(|| "[No Name]".to_owned(), |path| path.display().to_string());
+            self.pending_close = Some(action);
+            if self.state == State::Welcoming {
+                self.enter_editing();
+            }
+            self.open_prompt(Prompt::new(
+                PromptKind::UnsavedChanges,
+                format!("Save changes to {name}? [s]ave, [d]iscard, [c]ancel: "),
+            ));
+            return Ok(());
+        }
+        match action {
+            CloseAction::Quit => self.state = State::Exiting,
+            CloseAction::Open(path) => {
+                let (buffer, editorconfig) = Editor::load_file(path, self.encoding)?;
+                self.edit_area.set_buffer(buffer);
+                self.editorconfig = editorconfig;
+                self.apply_settings();
+            }
+        }
+        Ok(())
+    }
+
+    /// 处理关闭 buffer 之前询问是否保存修改的回答, `s` 保存之后关闭, `d` 丢弃修改并关闭, 其他回答取消关闭.
+    ///
+    /// buffer 没有对应的文件时, 保存会打开命令面板询问文件名, 在命令面板中保存之后再关闭.
+    fn answer_unsaved_changes(&mut self, answer: &str) -> error::Result<()> {
+        let Some(action) = self.pending_close.take() else {
+            return Ok(());
+        };
+        match answer {
+            "s" | "S" => {
+                self.execute(Command::Save, None)?;
+                if self.prompt.is_some() {
+                    // 正在询问文件名.
+                    self.pending_close = Some(action);
+                } else {
+                    self.close_after_save(action)?;
+                }
+            }
+            "d" | "D" => self.close_buffer(action, true)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 保存之后执行关闭操作, buffer 仍然有未保存的修改时 (比如在命令面板中执行了其他命令) 取消关闭.
+    fn close_after_save(&mut self, action: CloseAction) -> error::Result<()> {
+        if self.edit_area.get_buffer().is_modified() {
+            self.messages.push(Severity::Warning, "Close cancelled, the changes are not saved.");
+            return Ok(());
+        }
+        self.close_buffer(action, true)
+    }
+
+    /// 执行命令面板中输入的命令行, 格式见 [`command::parse_command_line`].
+    fn execute_command_line(&mut self, line: &str) -> error::Result<()> {
+        let (command, argument) = command::parse_command_line(line)?;
+        self.execute(command, argument)
+    }
+
+    /// 修改设置项, 格式为 `name=value`, 只有 `name` 时在状态栏中显示设置项的当前值,
+    /// 设置项见 [`settings::OPTIONS`].
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::UnknownOption`]: 设置项不存在, 比如名称拼写错误.
+    /// - [`error::Error::InvalidOptionValue`]: 设置的值不合法, 比如 `tabwidth=0`.
+    fn set_option(&mut self, assignment: &str) -> error::Result<()> {
+        let Some((name, value)) = assignment.split_once('=') else {
+            let name = assignment.trim();
+            let value = self.settings.get(name).ok_or_else(|| error::Error::UnknownOption(name.to_owned()))?;
+            self.messages.push(Severity::Info, format!("{name}={value}"));
+            return Ok(());
+        };
+        self.runtime_settings.set(name.trim(), value)?;
+        self.apply_settings();
+        Ok(())
+    }
+
+    /// 对 buffer 重复执行 count 次编辑操作, 操作失败时 (比如到达了 buffer 的开头或末尾) 停止重复并返回错误.
+    fn repeat_edit(&mut self, count: usize, f: impl Fn(&mut Buffer) -> error::Result<()>) -> error::Result<()> {
+        self.edit_area.edit(|buffer| (0..count).try_for_each(|_| f(buffer)))
+    }
+
+    /// 在每个光标处重复执行 count 次删除操作, 同 [`Editor::repeat_edit`], 见 [`EditArea::delete_at_cursors`].
+    ///
+    /// 有光标的操作失败时返回第一个错误, 其他光标仍然会执行.
+    fn repeat_delete(&mut self, count: usize, f: impl Fn(&mut Buffer) -> error::Result<()>) -> error::Result<()> {
+        let mut rst = Ok(());
+        self.edit_area.delete_at_cursors(|edit_area| {
+            let cursor_rst = edit_area.edit(|buffer| (0..count).try_for_each(|_| f(buffer)));
+            if rst.is_ok() {
+                rst = cursor_rst;
+            }
+        });
+        rst
+    }
+
+    /// 加载文件到新的 buffer 中, 并应用作用于此文件的 `.editorconfig`.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding`: 通过命令行指定的编码, 优先于 `.editorconfig` 中的 `charset`.
+    ///
+    /// # Errors
+    ///
+    /// 同 [`Buffer::load`].
+    fn load_file(path: impl AsRef<path::Path>, encoding: Option<&'static Encoding>) -> error::Result<(Buffer, EditorConfig)> {
+        let path = path.as_ref();
+        let editorconfig = EditorConfig::find(path);
+        let mut buffer = Buffer::new();
+        editorconfig.apply_before_load(&mut buffer);
+        if let Some(encoding) = encoding {
+            buffer.set_encoding(encoding);
+        }
+        buffer.load(path)?;
+        editorconfig.apply_after_load(&mut buffer);
+        Ok((buffer, editorconfig))
+    }
+
+    /// 根据正在编辑的文件的类型重新计算设置, 并应用到各个组件上.
+    ///
+    /// `.editorconfig` 没有指定缩进方式时, 使用从 buffer 的内容推测的缩进方式, 见 [`Buffer::detect_indent_style`].
+    ///
+    /// 在设置被修改和打开了其他文件之后调用.
+    fn apply_settings(&mut self) {
+        let buffer = self.edit_area.get_buffer();
+        self.filetype = buffer.path().and_then(Filetype::detect);
+        let mut file_settings = self.editorconfig.settings_layer();
+        file_settings.indent_style = file_settings.indent_style.or_else(|| buffer.detect_indent_style());
+        self.settings = self.settings_config.resolve(self.filetype.map(|t| t.name), &file_settings, &self.runtime_settings);
+        self.edit_area.set_settings(self.settings);
+        self.status_bar.set_style(self.settings.theme.status_bar());
+    }
+
+    /// 执行命令, 命令会根据 universal argument 重复执行或者作为参数.
+    ///
+    /// # Arguments
+    ///
+    /// * `command`: 要执行的命令.
+    /// * `argument`: 命令的参数, 见 [`Command::takes_argument`], 不接受参数的命令会把参数作为重复次数.
+    ///
+    /// 接受参数的命令在没有参数时会打开输入提示询问参数.
+    fn execute(&mut self, command: Command, argument: Option<&str>) -> error::Result<()> {
+        if command == Command::UniversalArgument {
+            self.universal_argument.start();
+            return Ok(());
+        }
+        let count = self.universal_argument.take();
+        let count = match argument {
+            Some(argument) if !command.takes_argument() => {
+                argument.parse().ok().filter(|count| *count > 0).ok_or_else(|| error::Error::InvalidArgument {
+                    command: command.to_string(),
+                    argument: argument.to_owned(),
+                })?
+            }
+            _ => count,
+        };
+        if self.readonly && (command.is_editing() || command.writes_file()) {
+            // 只读模式下不执行修改文本或者写入文件的命令, 只在状态栏中提示.
+            return Err(error::Error::ReadOnly);
+        }
+        if !command.keeps_cursors() {
+            self.edit_area.clear_cursors();
+        }
+        if !command.keeps_block() {
+            self.edit_area.cancel_block();
+        }
+        let continues_kill = self.last_command.is_some_and(|c| c.is_kill());
+        match command {
+            Command::Quit => self.close_buffer(CloseAction::Quit, false)?,
+            Command::ForceQuit => self.close_buffer(CloseAction::Quit, true)?,
+            Command::Save => {
+                let buffer = self.edit_area.get_buffer_mut();
+                match (argument, buffer.path().map(path::Path::to_path_buf)) {
+                    // buffer 没有对应的文件, 保存之后编辑此文件.
+                    (Some(path), None) => {
+                        buffer.save_as(path)?;
+                        self.apply_settings();
+                    }
+                    (Some(path), Some(_)) => buffer.save(path)?,
+                    (None, Some(path)) => buffer.save(path)?,
+                    // 启动时没有指定文件名, 询问文件名.
+                    (None, None) => self.open_command_palette("w "),
+                }
+            }
+            Command::Open => match argument {
+                Some(path) => self.close_buffer(CloseAction::Open(path.to_owned()), false)?,
+                None => self.open_command_palette("e "),
+            },
+            Command::Goto => match argument {
+                Some(target) => {
+                    let cursor = self.edit_area.goto(target.parse()?)?;
+                    self.terminal.move_cursor_to(cursor)?;
+                }
+                None => self.open_prompt(Prompt::new(PromptKind::Goto, "Go to line[:column]: ")),
+            },
+            Command::Set => match argument {
+                Some(assignment) => self.set_option(assignment)?,
+                None => self.open_command_palette("set "),
+            },
+            Command::Palette => self.open_command_palette(""),
+            Command::MoveCaret(caret_move) => {
+                self.edit_area.for_each_cursor(|edit_area| {
+                    for _ in 0..count {
+                        edit_area.move_caret(caret_move);
+                    }
+                });
+                self.terminal.move_cursor_to(self.edit_area.get_cursor())?;
+            }
+            Command::ScrollDown | Command::ScrollUp | Command::ScrollHalfPageDown | Command::ScrollHalfPageUp => {
+                let lines = match command {
+                    Command::ScrollDown | Command::ScrollUp => 1,
+                    _ => (self.edit_area.text_height() / 2).max(1),
+                };
+                let lines = (lines * count) as isize;
+                let down = matches!(command, Command::ScrollDown | Command::ScrollHalfPageDown);
+                self.edit_area.scroll_view(if down { lines } else { -lines });
+            }
+            Command::CenterCaret => self.edit_area.center_caret(),
+            Command::BlockSelect(caret_move) => {
+                let mut cursor = self.edit_area.block_select(caret_move);
+                for _ in 1..count {
+                    cursor = self.edit_area.block_select(caret_move);
+                }
+                self.terminal.move_cursor_to(cursor)?;
+            }
+            Command::DeleteBackward | Command::DeleteForward if self.edit_area.has_block() => {
+                for _ in 0..count {
+                    self.edit_area.block_delete(command == Command::DeleteBackward);
+                }
+            }
+            Command::InsertNewline => {
+                let indent_after = self.filetype.map_or(&[][..], |filetype| filetype.indent_after);
+                self.edit_area.insert_at_cursors(|edit_area| {
+                    for _ in 0..count {
+                        edit_area.insert_newline(indent_after);
+                    }
+                });
+            }
+            Command::InsertTab => {
+                let indent = self.edit_area.indent_unit().repeat(count);
+                self.edit_area.insert_at_cursors(|edit_area| write!(edit_area, "{indent}").unwrap());
+            }
+            Command::DeleteBackward => {
+                let mut rst = Ok(());
+                self.edit_area.delete_at_cursors(|edit_area| {
+                    let cursor_rst = (0..count).try_for_each(|_| edit_area.delete_backward());
+                    if rst.is_ok() {
+                        rst = cursor_rst;
+                    }
+                });
+                rst?;
+            }
+            Command::DeleteForward => self.repeat_delete(count, |buffer| buffer.delete_forward().map(|_| ()))?,
+            Command::DeleteWordBackward => self.repeat_delete(count, |buffer| buffer.delete_word_backward().map(|_| ()))?,
+            Command::DeleteWordForward => self.repeat_delete(count, |buffer| buffer.delete_word_forward().map(|_| ()))?,
+            Command::DeleteLine => self.repeat_edit(count, |buffer| buffer.delete_line().map(|_| ()))?,
+            Command::DuplicateLine => self.repeat_edit(count, Buffer::duplicate_line)?,
+            Command::MoveLineUp => self.repeat_edit(count, Buffer::move_line_up)?,
+            Command::MoveLineDown => self.repeat_edit(count, Buffer::move_line_down)?,
+            Command::JoinLines => self.repeat_edit(count, |buffer| buffer.join_lines().map(|_| ()))?,
+            Command::ToggleComment => match self.filetype.and_then(|filetype| filetype.comment) {
+                Some(comment) => {
+                    self.edit_area.edit(|buffer| buffer.toggle_comment(comment))?;
+                }
+                None => self.messages.push(Severity::Warning, "No comment syntax for this file type."),
+            },
+            Command::KillLine => {
+                if let Some(killed) = self.edit_area.kill_line(count) {
+                    self.push_kill(&killed, continues_kill);
+                }
+            }
+            Command::KillRegion | Command::CopyRegion if self.edit_area.has_block() => {
+                let text = if command == Command::KillRegion {
+                    self.edit_area.kill_block()
+                } else {
+                    self.edit_area.copy_block()
+                };
+                self.kill_ring.push_block(text.unwrap_or_default());
+            }
+            Command::KillRegion => {
+                if let Some(killed) = self.edit_area.kill_region() {
+                    self.push_kill(&killed, continues_kill);
+                }
+            }
+            Command::CopyRegion => {
+                if let Some(copied) = self.edit_area.copy_region() {
+                    self.push_kill(&copied, continues_kill);
+                }
+            }
+            Command::Yank => {
+                if let Some(text) = self.kill_ring.latest() {
+                    if self.kill_ring.latest_is_block() {
+                        self.edit_area.yank_block(text);
+                    } else {
+                        write!(self.edit_area, "{}", text.repeat(count)).unwrap();
+                    }
+                }
+            }
+            Command::SetMark => self.edit_area.for_each_cursor(EditArea::set_mark),
+            Command::Cancel => {
+                self.edit_area.unset_mark();
+                self.messages.dismiss();
+            }
+            Command::Messages => {
+                self.state = State::Messages;
+                self.edit_area.set_need_printing();
+            }
+            Command::AddCursorAbove => self.edit_area.add_cursor_above(),
+            Command::AddCursorBelow => self.edit_area.add_cursor_below(),
+            Command::AddNextOccurrence => self.edit_area.add_next_occurrence(),
+            Command::SplitSelectionIntoLines => self.edit_area.split_selection_into_lines(),
+            Command::UniversalArgument => unreachable!(),
+        }
+        self.last_command = Some(command);
+        Ok(())
+    }
+
+    /// 把被 kill 的文本放入 kill ring, 连续的 kill 会被合并到同一项.
+    fn push_kill(&mut self, text: &str, append: bool) {
+        if append {
+            self.kill_ring.append(text);
+        } else {
+            self.kill_ring.push(text.to_owned());
+        }
+    }
+
+    /// 根据 buffer 和 caret 的状态更新状态栏中的内容, 内容有变化时状态栏才会重新打印.
+    ///
+    /// 左侧显示文件名和修改标记, 右侧依次显示 caret 的行号和列号 (括号中为显示列), 总行数,
+    /// caret 所在行在文件中的百分比, 文件类型, 编码, 换行符和编辑模式.
+    fn update_status_bar(&mut self) {
+        let buffer = self.edit_area.get_buffer();
+        let padding = self.settings.status_padding;
+        let caret = buffer.caret();
+        let column = buffer.get(caret.y).map_or(0, |line| line[..caret.x].chars().count());
+        let lines_num = buffer.lines_num();
+        let name = buffer.path()
+            .map_or_else(|| "[No Name]".to_owned(), |path| path.display().to_string());
+        let mut encoding = buffer.encoding().name().to_owned();
+        if buffer.bom() {
+            encoding.push_str(" BOM");
+        }
+        let mode = if self.readonly {
+            "READ-ONLY".to_owned()
+        } else if self.edit_area.has_block() {
+            "BLOCK".to_owned()
+        } else if self.edit_area.has_multiple_cursors() {
+            format!("{} CURSORS", buffer.cursors().len() + 1)
+        } else if buffer.region().is_some() {
+            "SELECT".to_owned()
+        } else {
+            "EDIT".to_owned()
+        };
+
+        let mut segments = vec![Segment::new(name, Packing::Left(padding, 1))];
+        if buffer.is_modified() {
+            segments.push(Segment::new("[+]", Packing::Left(0, 1)));
+        }
+        let right = [
+            format!("{}:{} ({})", caret.y + 1, column + 1, self.edit_area.caret_column() + 1),
+            format!("{lines_num} lines"),
+            format!("{}%", (caret.y + 1) * 100 / lines_num.max(1)),
+            self.filetype.map_or("text", |filetype| filetype.name).to_owned(),
+            encoding,
+            buffer.line_ending().to_string(),
+            mode,
+        ];
+        let last = right.len() - 1;
+        segments.extend(right.into_iter().enumerate().map(|(i, text)| {
+            Segment::new(text, Packing::Right(1, if i == last { padding } else { 1 }))
+        }));
+        self.status_bar.set_segments(segments);
+        let message = self.messages.current(Instant::now())
+            .map(|message| (message.text.clone(), self.settings.theme.message(message.severity)));
+        self.status_bar.set_message(message);
+    }
+
     /// 检查子元素中是否有需要重新绘制的.
     fn check_need_printing(&self) -> bool {
         self.edit_area.need_printing()
@@ -245,32 +989,176 @@ impl Drop for Editor {
     fn drop(&mut self) {
         // 测试发现就算 panic 了这里也是会调用的.
         let _ = self.terminal.destruct();
-        println!("{} leaving...", CARGO_PKG_NAME);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
     use std::path::Path;
-    use crate::editor::{BufferLoadConfig, Editor, EditorBuildConfig};
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use crate::editor::{BufferLoadConfig, Editor, EditorBuildConfig, LineEnding, Location, Size, Terminal, VirtualTerminal};
+
+    /// 在 width x height 的虚拟终端中编辑文件.
+    fn open(path: &str, width: usize, height: usize) -> (Editor, VirtualTerminal) {
+        let config = EditorBuildConfig {
+            edit_text_config: BufferLoadConfig::File(Path::new(path)),
+            ..Default::default()
+        };
+        let terminal = VirtualTerminal::new(Size::new(width, height));
+        let editor = Editor::build_on(&config, Terminal::with(terminal.clone(), terminal.clone())).unwrap();
+        (editor, terminal)
+    }
 
     #[test]
     fn draw_in_split() {}
 
     #[test]
     fn scroll_vertical() {
-        let mut config = EditorBuildConfig::default();
-        config.edit_text_config = BufferLoadConfig::File(Path::new("example-vertical.txt"));
-        let mut editor = Editor::build(&config).unwrap();
-        editor.run().unwrap();
+        let (mut editor, terminal) = open("example-vertical.txt", 40, 10);
+        editor.run_pending().unwrap();
+        // 加载之后 caret 在文本末尾.
+        assert_eq!(terminal.line(2), "Paragraph 5:");
+        assert_eq!(terminal.cursor(), Some(Location::new(0, 8)));
+        terminal.push_key(KeyCode::Home, KeyModifiers::CONTROL);
+        editor.run_pending().unwrap();
+        assert_eq!(terminal.line(0), "Paragraph 1:");
+        assert_eq!(terminal.cursor(), Some(Location::new(0, 0)));
+        for _ in 0..20 {
+            terminal.push_key(KeyCode::Down, KeyModifiers::NONE);
+        }
+        editor.run_pending().unwrap();
+        assert_eq!(editor.buffer().caret(), Location::new(0, 20));
+        assert_eq!(terminal.line(0), "Paragraph 3:");
+        assert_eq!(terminal.cursor(), Some(Location::new(0, 6)));
+        assert!(terminal.line(9).starts_with(" 21:1 (1)  35 lines  60%"));
     }
 
     #[test]
     fn scroll_horizontal() {
-        let mut config = EditorBuildConfig::default();
-        config.edit_text_config = BufferLoadConfig::File(Path::new("example-horizontal.txt"));
-        let mut editor = Editor::build(&config).unwrap();
-        editor.run().unwrap();
+        let (mut editor, terminal) = open("example-horizontal.txt", 40, 10);
+        terminal.push_key(KeyCode::Home, KeyModifiers::CONTROL);
+        terminal.push_key(KeyCode::Down, KeyModifiers::NONE);
+        terminal.push_key(KeyCode::End, KeyModifiers::NONE);
+        editor.run_pending().unwrap();
+        assert_eq!(editor.buffer().caret(), Location::new(156, 1));
+        assert_eq!(terminal.line(1), "fect pangram for typing practice.\"");
+        assert_eq!(terminal.cursor(), Some(Location::new(34, 1)));
+        terminal.push_key(KeyCode::Home, KeyModifiers::NONE);
+        editor.run_pending().unwrap();
+        assert_eq!(terminal.line(0), "Paragraph 1:");
+        assert_eq!(terminal.line(1), "\"The quick brown fox jumps over the laz");
+        assert_eq!(terminal.cursor(), Some(Location::new(0, 1)));
+    }
+
+    #[test]
+    fn quit_and_resize() {
+        let (mut editor, terminal) = open("example-single-line.txt", 40, 10);
+        terminal.resize(Size::new(20, 5));
+        editor.run_pending().unwrap();
+        // caret 在行末, 为了保留水平边距而水平滚动.
+        assert_eq!(terminal.line(0), "World");
+        assert!(terminal.line(4).starts_with(" 1:12 (12)"));
+        terminal.type_str("!");
+        terminal.push_key(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        editor.run_pending().unwrap();
+        // 有未保存的修改, 询问是否保存.
+        assert!(!editor.is_exiting());
+        // 输入提示超出了宽度, 只显示靠近输入光标的部分.
+        assert!(terminal.line(4).ends_with("iscard, [c]ancel:"), "{}", terminal.line(4));
+        terminal.type_str("d");
+        terminal.type_str("ignored");
+        editor.run_pending().unwrap();
+        assert!(editor.is_exiting());
+        assert_eq!(terminal.pending_events(), 7);
+        assert_eq!(editor.buffer().to_string(), "Hello World!");
+    }
+
+    #[test]
+    fn quit_and_save_as() {
+        let path = std::env::temp_dir().join(format!("vegetor-save-as-{}.txt", std::process::id()));
+        let terminal = VirtualTerminal::new(Size::new(40, 10));
+        let mut editor = Editor::build_on(&EditorBuildConfig::default(), Terminal::with(terminal.clone(), terminal.clone())).unwrap();
+        terminal.type_str("hello");
+        terminal.push_key(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        // 没有文件名, 保存时在命令面板中询问文件名.
+        terminal.type_str("s");
+        editor.run_pending().unwrap();
+        assert!(!editor.is_exiting());
+        assert_eq!(terminal.line(9), ":w");
+        terminal.type_str(&format!("{}\n", path.display()));
+        editor.run_pending().unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(content, "hello");
+        assert!(editor.is_exiting());
+    }
+
+    #[test]
+    fn save_to_other_file() {
+        let path = std::env::temp_dir().join(format!("vegetor-save-other-{}.txt", std::process::id()));
+        let other = std::env::temp_dir().join(format!("vegetor-save-other-{}.copy.txt", std::process::id()));
+        fs::write(&path, "first").unwrap();
+        let config = EditorBuildConfig {
+            edit_text_config: BufferLoadConfig::File(&path),
+            ..Default::default()
+        };
+        let terminal = VirtualTerminal::new(Size::new(40, 10));
+        let mut editor = Editor::build_on(&config, Terminal::with(terminal.clone(), terminal.clone())).unwrap();
+        terminal.type_str("!");
+        terminal.push_key(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        terminal.type_str(&format!("w {}\n", other.display()));
+        // 保存到其他文件之后原文件仍然有未保存的修改.
+        terminal.push_key(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        editor.run_pending().unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        let other_content = fs::read_to_string(&other).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&other).unwrap();
+        assert_eq!(content, "first");
+        assert_eq!(other_content, "first!");
+        assert!(!editor.is_exiting());
+        assert!(terminal.line(9).contains("[s]ave, [d]iscard, [c]ancel"), "{}", terminal.line(9));
+        assert_eq!(editor.buffer().path(), Some(path.as_path()));
+    }
+
+    #[test]
+    fn open_with_encoding() {
+        let path = std::env::temp_dir().join(format!("vegetor-open-encoding-{}.txt", std::process::id()));
+        fs::write(&path, b"\xc4\xe3\xba\xc3").unwrap();
+        let config = EditorBuildConfig {
+            encoding: Some(encoding_rs::GBK),
+            ..Default::default()
+        };
+        let terminal = VirtualTerminal::new(Size::new(40, 10));
+        let mut editor = Editor::build_on(&config, Terminal::with(terminal.clone(), terminal.clone())).unwrap();
+        // 运行时打开的文件同样使用命令行指定的编码.
+        terminal.push_key(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        terminal.type_str(&format!("e {}\n", path.display()));
+        editor.run_pending().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(editor.buffer().get(0).map(String::as_str), Some("你好"));
+    }
+
+    #[test]
+    fn readonly_save() {
+        let path = std::env::temp_dir().join(format!("vegetor-readonly-{}.txt", std::process::id()));
+        fs::write(&path, "first\nsecond\n").unwrap();
+        let config = EditorBuildConfig {
+            edit_text_config: BufferLoadConfig::File(&path),
+            readonly: true,
+            // 保存时会改变换行符, 即使文本没有被修改.
+            line_ending: Some(LineEnding::CrLf),
+            ..Default::default()
+        };
+        let terminal = VirtualTerminal::new(Size::new(40, 10));
+        let mut editor = Editor::build_on(&config, Terminal::with(terminal.clone(), terminal.clone())).unwrap();
+        terminal.push_key(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        editor.run_pending().unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(content, "first\nsecond\n");
+        assert!(terminal.line(9).contains("The buffer is read-only."), "{}", terminal.line(9));
     }
 }
 
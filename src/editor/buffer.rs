@@ -1,17 +1,132 @@
-use std::iter::Sum;
-use std::path::Path;
-use crate::{error, CharsCount};
+use std::cmp::Ordering;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use crate::error;
 use std::{fmt, fs};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use crate::editor::filetype::Comment;
+use crate::editor::settings::IndentStyle;
 use crate::editor::terminal::{Size, Location};
 
-const LINE_SEP: &'static str = if cfg!(target_os = "windows") { "\r\n" } else { "\n" };
+/// 保存文件时使用的换行符.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum LineEnding {
+    /// `\n`.
+    Lf,
+    /// `\r\n`.
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    /// 根据文本中第一个换行符判断换行符的类型, 文本中没有换行符时使用操作系统默认的换行符.
+    fn detect(text: &str) -> LineEnding {
+        match text.find('\n') {
+            Some(idx) if text[..idx].ends_with('\r') => LineEnding::CrLf,
+            Some(_) => LineEnding::Lf,
+            None => LineEnding::default(),
+        }
+    }
+}
+
+impl Default for LineEnding {
+    fn default() -> Self {
+        if cfg!(target_os = "windows") { LineEnding::CrLf } else { LineEnding::Lf }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::CrLf => write!(f, "CRLF"),
+        }
+    }
+}
+
+/// 保存文件时对文本的处理, 见 [`Buffer::save`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+pub struct SaveOptions {
+    /// 删除每行末尾的空白字符.
+    pub trim_trailing_whitespace: bool,
+    /// 为 Some(true) 时保证文件以换行符结尾, 为 Some(false) 时保证文件不以换行符结尾, 为 None 时保持原样.
+    pub insert_final_newline: Option<bool>,
+}
+
+impl FromStr for LineEnding {
+    type Err = error::Error;
+
+    /// 解析 `lf` 或者 `crlf`, 不区分大小写.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::UnknownLineEnding`]
+    fn from_str(s: &str) -> error::Result<LineEnding> {
+        if s.eq_ignore_ascii_case("lf") {
+            Ok(LineEnding::Lf)
+        } else if s.eq_ignore_ascii_case("crlf") {
+            Ok(LineEnding::CrLf)
+        } else {
+            Err(error::Error::UnknownLineEnding(s.to_owned()))
+        }
+    }
+}
+
+/// 成对的括号, 见 [`Buffer::matching_bracket`].
+pub const BRACKET_PAIRS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}')];
+
+/// 一个光标, 即 caret 和 mark 组成的一组位置, 多光标编辑时每个光标都有各自的 caret 和选区.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Cursor {
+    pub caret: Location,
+    pub mark: Option<Location>,
+}
+
+impl Cursor {
+    pub fn new(caret: Location, mark: Option<Location>) -> Cursor {
+        Cursor { caret, mark }
+    }
+}
+
+/// 以 buffer 末尾为基准的位置, 见 [`Buffer::anchor`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Anchor {
+    /// 位置所在行之后的行数.
+    lines_after: usize,
+    /// 位置到行末的字节数.
+    bytes_after: usize,
+}
 
 #[derive(Debug)]
 /// 储存文本内容.
 pub struct Buffer {
     /// 当前写入 Buffer 的位置, 在 caret 索引的字符前进行输入, 不是终端的 cursor.
     caret: Location,
+    /// 选区的另一端, 和 caret 一起确定选区 (region).
+    mark: Option<Location>,
+    /// 除了 caret 和 mark 组成的主光标之外的其他光标, 按照添加的顺序排列.
+    cursors: Vec<Cursor>,
     lines: Vec<String>,
+    /// buffer 对应的文件路径, 从文件中加载时设置.
+    path: Option<PathBuf>,
+    /// 加载和保存文件时使用的编码.
+    encoding: &'static Encoding,
+    /// 保存文件时是否在文件开头写入 BOM, 加载文件时根据文件内容设置.
+    bom: bool,
+    /// 保存文件时使用的换行符, 加载文件时根据文件内容设置.
+    line_ending: LineEnding,
+    save_options: SaveOptions,
+    /// 内容被修改的次数, 每次修改 lines 时增加.
+    generation: u64,
+    /// 上一次加载或者保存时的 generation, 用于判断内容是否被修改, 见 [`Buffer::is_modified`].
+    saved_generation: u64,
 }
 
 /// [`Buffer`] 内容读取器, 在此读取器的生命周期时, buffer 内容不会改变.
@@ -24,30 +139,78 @@ impl Buffer {
     pub fn new() -> Buffer {
         let mut buffer = Buffer {
             caret: Location::default(),
+            mark: None,
+            cursors: Vec::new(),
             lines: Vec::new(),
+            path: None,
+            encoding: UTF_8,
+            bom: false,
+            line_ending: LineEnding::default(),
+            save_options: SaveOptions::default(),
+            generation: 0,
+            saved_generation: 0,
         };
         buffer.ensure_current_line(); // 要保证 buffer 不为空.
+        buffer.saved_generation = buffer.generation;
         buffer
     }
 
     /// 从文件中加载 Buffer, 加载完毕之后 caret 在末尾.
     /// 加载一个空文件会产生一个包含一个空行的 buffer.
+    ///
+    /// 文件使用 buffer 当前的编码进行解码, 如果文件开头有 BOM, 那么使用 BOM 对应的编码,
+    /// 换行符的类型根据文件内容设置.
+    ///
+    /// # Errors
+    ///
+    /// - [`io::Error`](std::io::Error).
+    /// - [`error::Error::MalformedText`]: 文件内容不是有效的当前编码的文本, 此时 buffer 的内容不会改变.
     pub fn load(&mut self, file: impl AsRef<Path>) -> error::Result<()> {
+        let bytes = fs::read(file.as_ref())?;
+        self.load_bytes(&bytes)?;
+        self.path = Some(file.as_ref().to_path_buf());
+        Ok(())
+    }
+
+    /// 读取 reader 中的全部内容到 Buffer 中, 比如从 stdin 读取, buffer 没有对应的文件路径.
+    ///
+    /// 解码方式和错误同 [`Buffer::load`].
+    pub fn load_from_reader(&mut self, mut reader: impl Read) -> error::Result<()> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        self.load_bytes(&bytes)
+    }
+
+    /// 解码并替换 Buffer 的内容, caret 在末尾.
+    fn load_bytes(&mut self, bytes: &[u8]) -> error::Result<()> {
+        let (s, encoding, malformed) = self.encoding.decode(bytes);
+        if malformed {
+            return Err(error::Error::MalformedText { encoding: encoding.name() });
+        }
         self.clear();
-        let s = fs::read_to_string(file)?;
+        self.path = None;
+        self.encoding = encoding;
+        self.bom = Encoding::for_bom(bytes).is_some();
+        self.line_ending = LineEnding::detect(&s);
         self.lines = s.split('\n').map(|x| x.trim_matches(|c| c == '\r' || c == '\n').to_string()).collect();
         let line_cnt = self.lines.len();
         if line_cnt == 0 {
             self.caret.x = 0;
             self.caret.y = 0;
         } else {
-            self.caret.x = self.lines.get(line_cnt - 1).unwrap().chars_count();
+            self.caret.x = self.lines.get(line_cnt - 1).unwrap().len();
             self.caret.y = line_cnt - 1;
         }
         self.ensure_current_line();
+        self.saved_generation = self.generation;
         Ok(())
     }
 
+    /// 内容在上一次加载或者保存之后是否被修改过, 只比较修改的次数, 改回原来的内容之后仍然视为修改过.
+    pub fn is_modified(&self) -> bool {
+        self.generation != self.saved_generation
+    }
+
     #[inline]
     pub fn get(&self, idx: usize) -> Option<&String> {
         self.lines.get(idx)
@@ -55,6 +218,7 @@ impl Buffer {
 
     #[inline]
     pub fn get_mut(&mut self, idx: usize) -> Option<&mut String> {
+        self.generation += 1;
         self.lines.get_mut(idx)
     }
 
@@ -100,7 +264,7 @@ impl Buffer {
             return Err(error::Error::CaretOutOfHeight { caret: caret.y, height: self.lines_num() });
         }
         let line = self.get(caret.y);
-        let len = if matches!(line, None) { 0 } else { line.unwrap().len() };
+        let len = line.map_or(0, String::len);
         if caret.x > len { // 允许等于, 以便在行末添加文本.
             Err(error::Error::CaretOutOfLen { caret: caret.x, len })
         } else {
@@ -108,17 +272,22 @@ impl Buffer {
         }
     }
 
+    /// 把位置限制到文本范围内最近的有效位置, 比如文本被修改之后, 之前记录的位置可能已经无效.
+    pub fn clamp_caret(&self, caret: Location) -> Location {
+        let y = caret.y.min(self.lines_num().saturating_sub(1));
+        let line = self.get(y).map_or("", String::as_str);
+        let mut x = caret.x.min(line.len());
+        while !line.is_char_boundary(x) {
+            x -= 1;
+        }
+        Location::new(x, y)
+    }
+
     /// 获取总行数.
     pub fn lines_num(&self) -> usize {
         self.lines.len()
     }
 
-    /// 获取 buffer 所代表的文本的字符数量.
-    pub fn len(&self) -> usize {
-        self.lines.iter().map(CharsCount::chars_count).sum::<usize>()
-            + self.lines_num().saturating_sub(1)
-    }
-
     /// 获取最长一行的宽度, todo 考虑要不要使用 width_cjk.
     pub fn max_width(&self) -> usize {
         match self.lines.iter().max_by_key(|x| x.len()) {
@@ -127,12 +296,30 @@ impl Buffer {
         }
     }
 
+    /// 根据行首的缩进推测文本使用的缩进方式, 以制表符开头的行和以至少两个空格开头的行哪种多就使用哪种.
+    ///
+    /// # Returns
+    ///
+    /// 没有缩进或者两种缩进一样多时返回 None.
+    pub fn detect_indent_style(&self) -> Option<IndentStyle> {
+        let tabs = self.lines.iter().filter(|line| line.starts_with('\t')).count();
+        let spaces = self.lines.iter().filter(|line| line.starts_with("  ")).count();
+        match tabs.cmp(&spaces) {
+            Ordering::Greater => Some(IndentStyle::Tab),
+            Ordering::Less => Some(IndentStyle::Space),
+            Ordering::Equal => None,
+        }
+    }
+
     /// 获取 Buffer 的二维占据尺寸, 使用的是 [`Buffer::max_width`] 和 [`Buffer::lines_num`].
     pub fn size(&self) -> Size {
         Size::new(self.max_width(), self.lines_num())
     }
 
-    /// 把 Buffer 内容保存到文件.
+    /// 把 Buffer 内容使用 buffer 的编码和换行符保存到文件, 并按照 [`SaveOptions`] 处理保存的文本,
+    /// buffer 中的内容不会被修改.
+    ///
+    /// 只有保存到 buffer 对应的文件时 buffer 才被视为没有修改, 保存到其他文件不影响 [`Buffer::is_modified`].
     ///
     /// # Arguments 
     ///
@@ -140,12 +327,90 @@ impl Buffer {
     ///
     /// # Errors
     ///
-    /// - [`io::Error`].
-    pub fn save(&self, path: impl AsRef<Path>) -> error::Result<()> {
-        fs::write(path, self.lines.join(LINE_SEP))?;
+    /// - [`io::Error`](std::io::Error).
+    /// - [`error::Error::UnencodableText`]: 文本中有当前编码无法表示的字符, 此时不会写入文件.
+    pub fn save(&mut self, path: impl AsRef<Path>) -> error::Result<()> {
+        let path = path.as_ref();
+        self.write_file(path)?;
+        if self.path.as_deref() == Some(path) {
+            self.saved_generation = self.generation;
+        }
+        Ok(())
+    }
+
+    /// 同 [`Buffer::save`], 保存成功之后把 path 作为 buffer 对应的文件.
+    ///
+    /// # Errors
+    ///
+    /// 同 [`Buffer::save`], 出错时 buffer 对应的文件不变.
+    pub fn save_as(&mut self, path: impl AsRef<Path>) -> error::Result<()> {
+        let path = path.as_ref();
+        self.write_file(path)?;
+        self.path = Some(path.to_path_buf());
+        self.saved_generation = self.generation;
         Ok(())
     }
 
+    /// 按照 [`Buffer::save`] 的规则把内容写入文件, 不改变 buffer 的状态.
+    fn write_file(&self, path: &Path) -> error::Result<()> {
+        let mut lines: Vec<&str> = self.lines.iter().map(String::as_str).collect();
+        if self.save_options.trim_trailing_whitespace {
+            lines.iter_mut().for_each(|line| *line = line.trim_end());
+        }
+        // 最后一行为空表示文本以换行符结尾.
+        let ends_with_newline = lines.len() > 1 && lines.last() == Some(&"");
+        match self.save_options.insert_final_newline {
+            Some(true) if !ends_with_newline && lines.iter().any(|line| !line.is_empty()) => lines.push(""),
+            Some(false) if ends_with_newline => {
+                lines.pop();
+            }
+            _ => {}
+        }
+        let text = lines.join(self.line_ending.as_str());
+        let mut bytes = Vec::new();
+        if self.bom {
+            bytes.extend(bom(self.encoding));
+        }
+        bytes.extend(encode(&text, self.encoding)?);
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// 设置保存文件时是否在文件开头写入 BOM, 只对 UTF-8 和 UTF-16 有效.
+    pub fn set_bom(&mut self, bom: bool) {
+        self.bom = bom;
+    }
+
+    /// 设置保存文件时对文本的处理.
+    pub fn set_save_options(&mut self, save_options: SaveOptions) {
+        self.save_options = save_options;
+    }
+
+    /// 设置加载和保存文件时使用的编码.
+    pub fn set_encoding(&mut self, encoding: &'static Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// 设置保存文件时使用的换行符.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// 加载和保存文件时使用的编码.
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// 保存文件时是否在文件开头写入 BOM.
+    pub fn bom(&self) -> bool {
+        self.bom
+    }
+
+    /// 保存文件时使用的换行符.
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
     /// 移动 caret 到指定位置.
     pub(crate) fn seek_unchecked(&mut self, caret_pos: Location) {
         self.caret = caret_pos;
@@ -155,22 +420,494 @@ impl Buffer {
     pub fn clear(&mut self) {
         self.caret.x = 0;
         self.caret.y = 0;
+        self.mark = None;
+        self.cursors.clear();
         self.lines.clear();
+        self.generation += 1;
     }
 
     pub fn caret(&self) -> Location {
         self.caret
     }
 
+    /// buffer 对应的文件路径.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// 设置 buffer 对应的文件路径, 比如在另存为时.
+    pub fn set_path(&mut self, path: impl Into<PathBuf>) {
+        self.path = Some(path.into());
+    }
+
+    /// 设置 mark, 传入 None 以取消 mark.
+    pub fn set_mark(&mut self, mark: Option<Location>) {
+        self.mark = mark;
+    }
+
+    /// 主光标, 即 caret 和 mark.
+    pub fn cursor(&self) -> Cursor {
+        Cursor::new(self.caret, self.mark)
+    }
+
+    /// 设置主光标, 不检查位置是否有效.
+    pub(crate) fn set_cursor(&mut self, cursor: Cursor) {
+        self.caret = cursor.caret;
+        self.mark = cursor.mark;
+    }
+
+    /// 除了主光标之外的其他光标.
+    pub fn cursors(&self) -> &[Cursor] {
+        &self.cursors
+    }
+
+    /// 添加一个光标, 如果已经有光标的 caret 在此位置, 那么不添加.
+    ///
+    /// # Returns
+    ///
+    /// 是否添加了光标.
+    pub fn add_cursor(&mut self, cursor: Cursor) -> bool {
+        if self.caret == cursor.caret || self.cursors.iter().any(|c| c.caret == cursor.caret) {
+            return false;
+        }
+        self.cursors.push(cursor);
+        true
+    }
+
+    /// 移除除了主光标之外的所有光标, 并返回这些光标.
+    pub fn take_cursors(&mut self) -> Vec<Cursor> {
+        std::mem::take(&mut self.cursors)
+    }
+
+    /// 把位置转换为以 buffer 末尾为基准的 [`Anchor`].
+    ///
+    /// 只修改位置之前的文本时, 位置之后的文本不变, 通过 [`Buffer::resolve_anchor`]
+    /// 可以得到修改之后原位置对应的新位置, 用于多光标编辑时调整其他光标的位置.
+    pub fn anchor(&self, location: Location) -> Anchor {
+        let line_len = self.get(location.y).map_or(0, String::len);
+        Anchor {
+            lines_after: self.lines_num().saturating_sub(location.y + 1),
+            bytes_after: line_len.saturating_sub(location.x),
+        }
+    }
+
+    /// 把 [`Anchor`] 转换回位置, 见 [`Buffer::anchor`], 得到的位置会被限制在文本范围内.
+    pub fn resolve_anchor(&self, anchor: Anchor) -> Location {
+        let y = self.lines_num().saturating_sub(anchor.lines_after + 1);
+        let x = self.get(y).map_or(0, |line| line.len().saturating_sub(anchor.bytes_after));
+        self.clamp_caret(Location::new(x, y))
+    }
+
+    /// 位置在全部文本中的字节偏移, 行之间以一个 `\n` 计算.
+    fn offset_of(&self, location: Location) -> usize {
+        self.lines[..location.y].iter().map(|line| line.len() + 1).sum::<usize>() + location.x
+    }
+
+    /// 全部文本中的字节偏移对应的位置, 是 [`Buffer::offset_of`] 的逆运算.
+    fn location_at(&self, mut offset: usize) -> Location {
+        for (y, line) in self.lines.iter().enumerate() {
+            if offset <= line.len() {
+                return Location::new(offset, y);
+            }
+            offset -= line.len() + 1;
+        }
+        self.end()
+    }
+
+    /// 从 from 开始向后查找 text, 到达 buffer 末尾时从头继续查找.
+    ///
+    /// # Returns
+    ///
+    /// 找到的文本的开始和结束位置, 没有找到或者 text 为空时返回 None.
+    pub fn find(&self, text: &str, from: Location) -> Option<(Location, Location)> {
+        if text.is_empty() || self.check_caret(from).is_err() {
+            return None;
+        }
+        let content = self.lines.join("\n");
+        let offset = self.offset_of(from);
+        let start = content[offset..].find(text)
+            .map(|idx| offset + idx)
+            .or_else(|| content.find(text))?;
+        Some((self.location_at(start), self.location_at(start + text.len())))
+    }
+
+    /// 位置处的单词的开始和结束位置, 单词由字母, 数字和下划线组成.
+    ///
+    /// 位置在单词末尾时也返回这个单词, 位置前后都不是单词时返回 None.
+    pub fn word_at(&self, location: Location) -> Option<(Location, Location)> {
+        let line = self.get(location.y)?;
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let before = line.get(..location.x)?;
+        let start = before.char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word(*c))
+            .last()
+            .map_or(location.x, |(idx, _)| idx);
+        let after = &line[location.x..];
+        let end = location.x + after.find(|c| !is_word(c)).unwrap_or(after.len());
+        (start < end).then(|| (Location::new(start, location.y), Location::new(end, location.y)))
+    }
+
+    /// 获取 mark 和 caret 之间的区域, 返回的两个位置按照文本中的先后顺序排列.
+    ///
+    /// 如果没有设置 mark, 或者 mark 因为文本的修改而不再有效, 返回 None.
+    pub fn region(&self) -> Option<(Location, Location)> {
+        let mark = self.mark?;
+        self.check_caret(mark).ok()?;
+        Some((mark.min(self.caret), mark.max(self.caret)))
+    }
+
+    /// 检查 start 和 end 是否构成有效的文本范围.
+    fn check_range(&self, start: Location, end: Location) -> error::Result<()> {
+        self.check_caret(start)?;
+        self.check_caret(end)?;
+        if start > end {
+            Err(error::Error::InvalidRange { start, end })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 获取 [start, end) 范围内的文本, 行之间使用 `\n` 连接.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::CaretOutOfHeight`]
+    /// - [`error::Error::CaretOutOfLen`]
+    /// - [`error::Error::InvalidRange`]: start 在 end 之后.
+    pub fn text_range(&self, start: Location, end: Location) -> error::Result<String> {
+        self.check_range(start, end)?;
+        if start.y == end.y {
+            return Ok(self.lines[start.y][start.x..end.x].to_owned());
+        }
+        let mut text = self.lines[start.y][start.x..].to_owned();
+        for line in &self.lines[start.y + 1..end.y] {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text.push('\n');
+        text.push_str(&self.lines[end.y][..end.x]);
+        Ok(text)
+    }
+
+    /// 删除 [start, end) 范围内的文本, 删除之后 caret 位于 start.
+    ///
+    /// # Returns
+    ///
+    /// - Ok(String): 被删除的文本.
+    /// - Err(e): 同 [`Buffer::text_range`].
+    pub fn delete_range(&mut self, start: Location, end: Location) -> error::Result<String> {
+        let removed = self.text_range(start, end)?;
+        self.generation += 1;
+        let tail = self.lines[end.y][end.x..].to_owned();
+        self.lines.drain(start.y + 1..=end.y);
+        let line = &mut self.lines[start.y];
+        line.truncate(start.x);
+        line.push_str(&tail);
+        self.caret = start;
+        Ok(removed)
+    }
+
+    /// buffer 末尾的位置, 即最后一行的行末.
+    pub fn end(&self) -> Location {
+        let y = self.lines.len().saturating_sub(1);
+        Location::new(self.lines.get(y).map_or(0, String::len), y)
+    }
+
+    /// 删除 caret 指向的字符, caret 在行末时删除换行符, caret 位置不变.
+    ///
+    /// # Returns
+    ///
+    /// - Ok(char): 被删除的字符.
+    /// - Err(e):
+    ///     - [`error::Error::CaretOutOfHeight`]
+    ///     - [`error::Error::CaretOutOfLen`]
+    ///     - [`error::Error::EndOfFile`]: caret 在 buffer 末尾.
+    pub fn delete_forward(&mut self) -> error::Result<char> {
+        self.check_self_caret()?;
+        let y = self.caret.y;
+        if self.caret.x < self.lines[y].len() {
+            self.generation += 1;
+            Ok(self.lines[y].remove(self.caret.x))
+        } else if y + 1 < self.lines.len() {
+            self.generation += 1;
+            let next = self.lines.remove(y + 1);
+            self.lines[y].push_str(&next);
+            Ok('\n')
+        } else {
+            Err(error::Error::EndOfFile)
+        }
+    }
+
+    /// 删除 caret 之前的一个单词以及单词和 caret 之间的空白, 单词之间以空白分隔.
+    ///
+    /// # Returns
+    ///
+    /// - Ok(String): 被删除的文本.
+    /// - Err(e):
+    ///     - [`error::Error::CaretOutOfHeight`]
+    ///     - [`error::Error::CaretOutOfLen`]
+    ///     - [`error::Error::DelAtBeginning`]: caret 在 buffer 开头.
+    pub fn delete_word_backward(&mut self) -> error::Result<String> {
+        let mut reader = self.get_reader()?;
+        let start = if reader.back_until_not_blank().is_ok() && reader.back_until_blank().is_ok() {
+            reader.caret()
+        } else {
+            // 之前只有空白, 或者单词之前没有空白, 删除到 buffer 开头.
+            Location::new(0, 0)
+        };
+        if start == self.caret {
+            return Err(error::Error::DelAtBeginning);
+        }
+        self.delete_range(start, self.caret)
+    }
+
+    /// 删除 caret 之后的空白以及一个单词, 单词之间以空白分隔, caret 位置不变.
+    ///
+    /// # Returns
+    ///
+    /// - Ok(String): 被删除的文本.
+    /// - Err(e):
+    ///     - [`error::Error::CaretOutOfHeight`]
+    ///     - [`error::Error::CaretOutOfLen`]
+    ///     - [`error::Error::EndOfFile`]: caret 在 buffer 末尾.
+    pub fn delete_word_forward(&mut self) -> error::Result<String> {
+        let mut reader = self.get_reader()?;
+        let end = if reader.skip_until_not_blank().is_ok() && reader.skip_until_blank().is_ok() {
+            reader.caret()
+        } else {
+            // 之后只有空白, 或者单词之后没有空白, 删除到 buffer 末尾.
+            self.end()
+        };
+        if end == self.caret {
+            return Err(error::Error::EndOfFile);
+        }
+        self.delete_range(self.caret, end)
+    }
+
+    /// 删除 caret 所在的行, caret 移动到下一行的行首, 删除的是最后一行时移动到上一行的行首.
+    ///
+    /// buffer 只有一行时清空这一行的内容.
+    ///
+    /// # Returns
+    ///
+    /// - Ok(String): 被删除的文本, 包括被删除的换行符.
+    /// - Err(e):
+    ///     - [`error::Error::CaretOutOfHeight`]
+    ///     - [`error::Error::CaretOutOfLen`]
+    pub fn delete_line(&mut self) -> error::Result<String> {
+        self.check_self_caret()?;
+        let y = self.caret.y;
+        self.generation += 1;
+        if self.lines.len() == 1 {
+            self.caret = Location::new(0, 0);
+            return Ok(std::mem::take(&mut self.lines[0]));
+        }
+        let line = self.lines.remove(y);
+        if y == self.lines.len() {
+            self.caret = Location::new(0, y - 1);
+            Ok(format!("\n{line}"))
+        } else {
+            self.caret = Location::new(0, y);
+            Ok(format!("{line}\n"))
+        }
+    }
+
+    /// 在 caret 所在行之后插入一行相同的内容, caret 移动到新的行中相同的位置.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::CaretOutOfHeight`]
+    /// - [`error::Error::CaretOutOfLen`]
+    pub fn duplicate_line(&mut self) -> error::Result<()> {
+        self.check_self_caret()?;
+        let line = self.lines[self.caret.y].clone();
+        self.generation += 1;
+        self.lines.insert(self.caret.y + 1, line);
+        self.caret.y += 1;
+        Ok(())
+    }
+
+    /// 交换 caret 所在行和上一行, caret 跟随所在行移动.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::CaretOutOfHeight`]
+    /// - [`error::Error::CaretOutOfLen`]
+    /// - [`error::Error::BeginningOfFile`]: caret 在第一行.
+    pub fn move_line_up(&mut self) -> error::Result<()> {
+        self.check_self_caret()?;
+        if self.caret.y == 0 {
+            return Err(error::Error::BeginningOfFile);
+        }
+        self.generation += 1;
+        self.lines.swap(self.caret.y - 1, self.caret.y);
+        self.caret.y -= 1;
+        Ok(())
+    }
+
+    /// 交换 caret 所在行和下一行, caret 跟随所在行移动.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::CaretOutOfHeight`]
+    /// - [`error::Error::CaretOutOfLen`]
+    /// - [`error::Error::EndOfFile`]: caret 在最后一行.
+    pub fn move_line_down(&mut self) -> error::Result<()> {
+        self.check_self_caret()?;
+        if self.caret.y + 1 >= self.lines.len() {
+            return Err(error::Error::EndOfFile);
+        }
+        self.generation += 1;
+        self.lines.swap(self.caret.y, self.caret.y + 1);
+        self.caret.y += 1;
+        Ok(())
+    }
+
+    /// 把下一行连接到 caret 所在行的末尾, 连接处两侧的空白被替换为一个空格,
+    /// 有一侧没有内容时不插入空格. caret 移动到连接处.
+    ///
+    /// # Returns
+    ///
+    /// - Ok(String): 被删除的文本, 即换行符和连接处两侧的空白.
+    /// - Err(e):
+    ///     - [`error::Error::CaretOutOfHeight`]
+    ///     - [`error::Error::CaretOutOfLen`]
+    ///     - [`error::Error::EndOfFile`]: caret 在最后一行.
+    pub fn join_lines(&mut self) -> error::Result<String> {
+        self.check_self_caret()?;
+        let y = self.caret.y;
+        let Some(next) = self.lines.get(y + 1) else {
+            return Err(error::Error::EndOfFile);
+        };
+        let start = Location::new(self.lines[y].trim_end().len(), y);
+        let end = Location::new(next.len() - next.trim_start().len(), y + 1);
+        let separated = start.x > 0 && end.x < next.len();
+        let removed = self.delete_range(start, end)?;
+        if separated {
+            self.lines[y].insert(start.x, ' ');
+        }
+        Ok(removed)
+    }
+
+    /// 切换 caret 所在行或者选区所在的各行的注释, 所有的修改作为一次编辑完成, 缩进保持不变.
+    ///
+    /// - [`Comment::Line`]: 所有非空行都已经被注释时, 删除注释标记和其后的一个空格,
+    ///   否则在所有非空行的最小缩进处插入注释标记和一个空格.
+    /// - [`Comment::Block`]: 第一个非空行以开始标记开头且最后一个非空行以结束标记结尾时, 删除这两个标记,
+    ///   否则在第一个非空行的缩进之后插入开始标记, 在最后一个非空行的末尾插入结束标记.
+    ///
+    /// 选区结束于某一行的行首时不包括这一行. caret 和 mark 会跟随所在位置的文本移动.
+    ///
+    /// # Returns
+    ///
+    /// - Ok(String): 修改前的各行, 使用 `\n` 连接.
+    /// - Err(e):
+    ///     - [`error::Error::CaretOutOfHeight`]
+    ///     - [`error::Error::CaretOutOfLen`]
+    pub fn toggle_comment(&mut self, comment: Comment) -> error::Result<String> {
+        self.check_self_caret()?;
+        let (start, end) = self.region().unwrap_or((self.caret, self.caret));
+        let last = if end.y > start.y && end.x == 0 { end.y - 1 } else { end.y };
+        let old = self.lines[start.y..=last].join("\n");
+        let non_blank: Vec<usize> = (start.y..=last).filter(|y| !self.lines[*y].trim().is_empty()).collect();
+        let (Some(&first), Some(&last)) = (non_blank.first(), non_blank.last()) else {
+            return Ok(old);
+        };
+        let indent_len = |line: &str| line.len() - line.trim_start().len();
+        // 每一处修改为 (行, 字节位置, 删除的长度, 插入的文本), 同一行中靠后的修改在前.
+        let mut edits: Vec<(usize, usize, usize, String)> = Vec::new();
+        match comment {
+            Comment::Line(token) => {
+                let commented = non_blank.iter().all(|y| self.lines[*y].trim_start().starts_with(token));
+                let column = non_blank.iter().map(|y| indent_len(&self.lines[*y])).min().unwrap();
+                for y in non_blank {
+                    let line = &self.lines[y];
+                    let x = indent_len(line);
+                    if commented {
+                        let len = token.len() + usize::from(line[x + token.len()..].starts_with(' '));
+                        edits.push((y, x, len, String::new()));
+                    } else {
+                        edits.push((y, column, 0, format!("{token} ")));
+                    }
+                }
+            }
+            Comment::Block(open, close) => {
+                let first_line = &self.lines[first];
+                let x = indent_len(first_line);
+                let last_line = self.lines[last].trim_end();
+                let commented = first_line[x..].starts_with(open)
+                    && last_line.ends_with(close)
+                    && (first != last || last_line.len() - x >= open.len() + close.len());
+                if commented {
+                    let close_start = last_line.len() - close.len();
+                    let close_len = close.len() + usize::from(last_line[..close_start].ends_with(' '));
+                    edits.push((last, last_line.len() - close_len, close_len, String::new()));
+                    let open_len = open.len() + usize::from(first_line[x + open.len()..].starts_with(' '));
+                    edits.push((first, x, open_len, String::new()));
+                } else {
+                    edits.push((last, last_line.len(), 0, format!(" {close}")));
+                    edits.push((first, x, 0, format!("{open} ")));
+                }
+            }
+        }
+        self.generation += 1;
+        for (y, x, len, text) in edits {
+            self.lines[y].replace_range(x..x + len, &text);
+            let adjust = |location: &mut Location| {
+                if location.y == y && location.x > x {
+                    location.x = location.x.max(x + len) - len + text.len();
+                }
+            };
+            adjust(&mut self.caret);
+            if let Some(mark) = &mut self.mark {
+                adjust(mark);
+            }
+        }
+        Ok(old)
+    }
+
+    /// 查找 at 处的括号对应的另一个括号的位置, 会跨行扫描, 并跳过嵌套的同种括号.
+    ///
+    /// at 处不是 [`BRACKET_PAIRS`] 中的括号, 或者没有对应的括号时返回 None.
+    pub fn matching_bracket(&self, at: Location) -> Option<Location> {
+        self.check_caret(at).ok()?;
+        let mut reader = BufferReader { caret: at, buffer: self };
+        let bracket = reader.peek()?;
+        if let Some(&(open, close)) = BRACKET_PAIRS.iter().find(|(open, _)| *open == bracket) {
+            reader.next();
+            let mut depth = 0;
+            loop {
+                let location = reader.caret();
+                match reader.next()? {
+                    c if c == open => depth += 1,
+                    c if c == close && depth == 0 => return Some(location),
+                    c if c == close => depth -= 1,
+                    _ => {}
+                }
+            }
+        }
+        let &(open, close) = BRACKET_PAIRS.iter().find(|(_, close)| *close == bracket)?;
+        let mut depth = 0;
+        loop {
+            match reader.prev()? {
+                c if c == close => depth += 1,
+                c if c == open && depth == 0 => return Some(reader.caret()),
+                c if c == open => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
     /// 获取一个字符读取器, 从 caret 的位置开始读取.
     ///
     /// # Errors
     ///
     /// - [`error::Error::CaretOutOfHeight`]
     /// - [`error::Error::CaretOutOfLen`]
-    pub fn get_reader(&self) -> error::Result<BufferReader> {
+    pub fn get_reader(&self) -> error::Result<BufferReader<'_>> {
         self.check_self_caret()?;
-        Ok(BufferReader::new(&self))
+        Ok(BufferReader::new(self))
     }
 
     /// 删除 buffer 中 caret 指向的字符的前一个字符.
@@ -199,14 +936,47 @@ impl Buffer {
                 Err(error::Error::DelAtBeginning)
             }
         } else {
-            self.caret.x -= 1;
-            let x = self.caret.x;
-            let line = self.get_current_line_mut().unwrap();
-            Ok(line.remove(x))
+            self.generation += 1;
+            let line = self.lines.get_mut(self.caret.y).unwrap();
+            // caret.x 是字节位置, 需要删除前面的一整个字符.
+            let (idx, _) = line[..self.caret.x].char_indices().next_back().unwrap();
+            self.caret.x = idx;
+            Ok(line.remove(idx))
         }
     }
 }
 
+/// 编码对应的 BOM, 没有 BOM 的编码返回空.
+fn bom(encoding: &'static Encoding) -> &'static [u8] {
+    if encoding == UTF_8 {
+        b"\xef\xbb\xbf"
+    } else if encoding == UTF_16LE {
+        b"\xff\xfe"
+    } else if encoding == UTF_16BE {
+        b"\xfe\xff"
+    } else {
+        b""
+    }
+}
+
+/// 把文本编码为指定编码的字节.
+///
+/// [`Encoding::encode`] 会把 UTF-16 的文本编码为 UTF-8, 所以 UTF-16 需要单独处理.
+fn encode(text: &str, encoding: &'static Encoding) -> error::Result<Vec<u8>> {
+    if encoding == UTF_16LE {
+        return Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect());
+    }
+    if encoding == UTF_16BE {
+        return Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect());
+    }
+    let (bytes, _, unmappable) = encoding.encode(text);
+    if unmappable {
+        Err(error::Error::UnencodableText { encoding: encoding.name() })
+    } else {
+        Ok(bytes.into_owned())
+    }
+}
+
 impl<'a> BufferReader<'a> {
     fn new(buffer: &'a Buffer) -> BufferReader<'a> {
         BufferReader {
@@ -335,7 +1105,7 @@ impl<'a> BufferReader<'a> {
         } else {
             let line = self.buffer.get(self.caret.y).unwrap();
             let line = &line[..self.caret.x];
-            let ch = line.chars().rev().next().unwrap();
+            let ch = line.chars().next_back().unwrap();
             self.caret.x -= ch.len_utf8();
             Some(ch)
         }
@@ -363,15 +1133,16 @@ impl<'a> BufferReader<'a> {
 
 impl fmt::Write for Buffer {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        self.check_self_caret().or_else(|_| Err(fmt::Error))?;
+        self.check_self_caret().map_err(|_| fmt::Error)?;
+        self.generation += 1;
         for c in s.chars() {
-            if !c.is_control() && c != '\r' {
+            if (!c.is_control() || c == '\t') && c != '\r' {
                 let line = self.lines.get_mut(self.caret.y).unwrap();
                 line.insert(self.caret.x, c);
-                self.caret.x += 1;
+                self.caret.x += c.len_utf8();
             } else if c == '\n' {
                 let line = self.lines.get_mut(self.caret.y).unwrap();
-                let to_move = (&line[self.caret.x..]).to_owned();
+                let to_move = line[self.caret.x..].to_owned();
                 line.truncate(self.caret.x);
                 self.caret.y += 1;
                 self.caret.x = 0;
@@ -390,11 +1161,19 @@ impl fmt::Display for Buffer {
 
 #[cfg(test)]
 mod tests {
-    use crate::editor::buffer::Buffer;
+    use crate::editor::buffer::{Buffer, Cursor, LineEnding, SaveOptions};
+    use crate::editor::filetype::Comment;
+    use crate::editor::settings::IndentStyle;
     use std::fmt::Write;
     use std::fs;
+    use std::path::PathBuf;
     use crate::editor::buffer::Location;
 
+    /// 测试中写入的临时文件, 名称包含进程 id, 以免和同时运行的其他测试冲突.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("vegetor-{name}-{}.txt", std::process::id()))
+    }
+
     #[test]
     fn load_and_save() {
         let mut buffer = Buffer::new();
@@ -412,6 +1191,88 @@ mod tests {
         );
     }
 
+    #[test]
+    fn encoding_and_line_ending() {
+        let path = &temp_path("encoding");
+        fs::write(path, b"\xc4\xe3\xba\xc3\r\nfoo").unwrap();
+        let mut buffer = Buffer::new();
+        assert!(buffer.load(path).is_err());
+        buffer.set_encoding(encoding_rs::GBK);
+        buffer.load(path).unwrap();
+        assert_eq!("你好\nfoo", format!("{}", buffer));
+        buffer.save(path).unwrap();
+        assert_eq!(fs::read(path).unwrap(), b"\xc4\xe3\xba\xc3\r\nfoo");
+        write!(buffer, "😀").unwrap();
+        assert!(buffer.save(path).is_err());
+        buffer.set_encoding(encoding_rs::UTF_16LE);
+        buffer.set_line_ending(LineEnding::Lf);
+        buffer.save(path).unwrap();
+        assert_eq!(fs::read(path).unwrap()[..6], [0x60, 0x4f, 0x7d, 0x59, b'\n', 0]);
+        assert_eq!("lf".parse::<LineEnding>().unwrap(), LineEnding::Lf);
+        assert_eq!("CRLF".parse::<LineEnding>().unwrap(), LineEnding::CrLf);
+        assert!("cr".parse::<LineEnding>().is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn save_options() {
+        let path = &temp_path("save-options");
+        let mut buffer = Buffer::new();
+        buffer.set_line_ending(LineEnding::Lf);
+        write!(buffer, "foo  \n\tbar\t").unwrap();
+        buffer.set_save_options(SaveOptions { trim_trailing_whitespace: true, insert_final_newline: Some(true) });
+        buffer.save(path).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "foo\n\tbar\n");
+
+        buffer.load(path).unwrap();
+        buffer.set_save_options(SaveOptions { trim_trailing_whitespace: false, insert_final_newline: Some(false) });
+        buffer.set_bom(true);
+        buffer.save(path).unwrap();
+        assert_eq!(fs::read(path).unwrap(), b"\xef\xbb\xbffoo\n\tbar");
+        buffer.load(path).unwrap();
+        buffer.set_save_options(SaveOptions::default());
+        buffer.save(path).unwrap();
+        assert_eq!(fs::read(path).unwrap(), b"\xef\xbb\xbffoo\n\tbar");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn modified() {
+        let path = &temp_path("modified");
+        let mut buffer = Buffer::new();
+        assert!(!buffer.is_modified());
+        write!(buffer, "foo").unwrap();
+        assert!(buffer.is_modified());
+        // 保存到其他文件不改变修改状态.
+        buffer.save(path).unwrap();
+        assert!(buffer.is_modified());
+        buffer.save_as(path).unwrap();
+        assert!(!buffer.is_modified());
+        assert_eq!(buffer.path(), Some(path.as_path()));
+        // 失败的修改不改变内容.
+        assert!(buffer.move_line_up().is_err());
+        assert!(!buffer.is_modified());
+        buffer.del_char().unwrap();
+        assert!(buffer.is_modified());
+        // 改回原来的内容之后仍然视为修改过.
+        write!(buffer, "o").unwrap();
+        assert!(buffer.is_modified());
+        buffer.delete_line().unwrap();
+        buffer.load(path).unwrap();
+        assert!(!buffer.is_modified());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_from_reader() {
+        let mut buffer = Buffer::new();
+        buffer.load("example-single-line.txt").unwrap();
+        buffer.load_from_reader("foo\r\nbar".as_bytes()).unwrap();
+        assert_eq!("foo\nbar", format!("{}", buffer));
+        assert_eq!(buffer.path(), None);
+        assert_eq!(buffer.caret(), Location::new(3, 1));
+    }
+
     #[test]
     fn when_empty() {
         let mut buffer = Buffer::new();
@@ -439,17 +1300,10 @@ mod tests {
         let mut buffer = Buffer::new();
         buffer.load("example-horizontal.txt").unwrap();
         buffer.seek_unchecked(Location::new(0, 0));
-        let mut reader = buffer.get_reader().unwrap();
+        let reader = buffer.get_reader().unwrap();
         let mut string = String::new();
-        loop {
-            match reader.next() {
-                Some(ch) => {
-                    write!(string, "{}", ch).unwrap();
-                }
-                None => {
-                    break;
-                }
-            }
+        for ch in reader {
+            write!(string, "{}", ch).unwrap();
         }
         assert_eq!(string, format!("{}", buffer));
     }
@@ -459,14 +1313,9 @@ mod tests {
         let mut buffer = Buffer::new();
         buffer.load("example-horizontal.txt").unwrap();
         let mut reader = buffer.get_reader().unwrap();
-        let mut string = String::with_capacity(buffer.len());
-        loop {
-            match reader.prev() {
-                Some(ch) => {
-                    write!(string, "{}", ch).unwrap();
-                }
-                None => break,
-            }
+        let mut string = String::new();
+        while let Some(ch) = reader.prev() {
+            write!(string, "{}", ch).unwrap();
         }
         let string: String = string.chars().rev().collect();
         assert_eq!(string, format!("{}", buffer));
@@ -484,5 +1333,165 @@ mod tests {
             "a\nc",
             format!("{}", buffer)
         );
+        write!(buffer, "中\t文").unwrap();
+        assert_eq!(buffer.del_char().unwrap(), '文');
+        assert_eq!(buffer.del_char().unwrap(), '\t');
+        assert_eq!(buffer.caret(), Location::new(3, 1));
+        assert_eq!("a\n中c", format!("{}", buffer));
+    }
+
+    #[test]
+    fn detect_indent_style() {
+        let mut buffer = Buffer::new();
+        write!(buffer, "all:\n\tcc main.c\n\tcc lib.c\n").unwrap();
+        assert_eq!(buffer.detect_indent_style(), Some(IndentStyle::Tab));
+        write!(buffer, "  a\n  b\n    c\n").unwrap();
+        assert_eq!(buffer.detect_indent_style(), Some(IndentStyle::Space));
+        let mut buffer = Buffer::new();
+        write!(buffer, "a\n b").unwrap();
+        assert_eq!(buffer.detect_indent_style(), None);
+    }
+
+    #[test]
+    fn delete_chars_and_words() {
+        let mut buffer = Buffer::new();
+        write!(buffer, "foo bar  baz\nqux").unwrap();
+        buffer.seek_unchecked(Location::new(0, 0));
+        assert_eq!(buffer.delete_forward().unwrap(), 'f');
+        assert_eq!(buffer.delete_word_forward().unwrap(), "oo");
+        assert_eq!(buffer.delete_word_forward().unwrap(), " bar");
+        assert_eq!(buffer.caret(), Location::new(0, 0));
+        assert_eq!(buffer.to_string(), "  baz\nqux");
+
+        buffer.seek_unchecked(Location::new(5, 0));
+        assert_eq!(buffer.delete_forward().unwrap(), '\n');
+        assert_eq!(buffer.to_string(), "  bazqux");
+        buffer.seek_unchecked(Location::new(8, 0));
+        assert!(buffer.delete_forward().is_err());
+        assert_eq!(buffer.delete_word_backward().unwrap(), "bazqux");
+        assert_eq!(buffer.delete_word_backward().unwrap(), "  ");
+        assert!(buffer.delete_word_backward().is_err());
+        assert!(buffer.delete_word_forward().is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn line_operations() {
+        let mut buffer = Buffer::new();
+        write!(buffer, "a\n  b\nc").unwrap();
+        buffer.seek_unchecked(Location::new(1, 1));
+        buffer.duplicate_line().unwrap();
+        assert_eq!(buffer.to_string(), "a\n  b\n  b\nc");
+        assert_eq!(buffer.caret(), Location::new(1, 2));
+        buffer.move_line_down().unwrap();
+        assert!(buffer.move_line_down().is_err());
+        assert_eq!(buffer.to_string(), "a\n  b\nc\n  b");
+        assert_eq!(buffer.delete_line().unwrap(), "\n  b");
+        assert_eq!(buffer.caret(), Location::new(0, 2));
+        buffer.move_line_up().unwrap();
+        buffer.move_line_up().unwrap();
+        assert!(buffer.move_line_up().is_err());
+        assert_eq!(buffer.to_string(), "c\na\n  b");
+        assert_eq!(buffer.delete_line().unwrap(), "c\n");
+
+        assert_eq!(buffer.join_lines().unwrap(), "\n  ");
+        assert_eq!(buffer.to_string(), "a b");
+        assert_eq!(buffer.caret(), Location::new(1, 0));
+        assert!(buffer.join_lines().is_err());
+        assert_eq!(buffer.delete_line().unwrap(), "a b");
+        assert_eq!(buffer.lines_num(), 1);
+    }
+
+    #[test]
+    fn toggle_comment() {
+        let mut buffer = Buffer::new();
+        write!(buffer, "fn main() {{\n    let a = 1;\n\n        a\n}}").unwrap();
+        buffer.seek_unchecked(Location::new(0, 1));
+        buffer.set_mark(Some(Location::new(0, 4)));
+        assert_eq!(buffer.toggle_comment(Comment::Line("//")).unwrap(), "    let a = 1;\n\n        a");
+        assert_eq!(buffer.to_string(), "fn main() {\n    // let a = 1;\n\n    //     a\n}");
+        assert_eq!(buffer.region(), Some((Location::new(0, 1), Location::new(0, 4))));
+        buffer.toggle_comment(Comment::Line("//")).unwrap();
+        assert_eq!(buffer.to_string(), "fn main() {\n    let a = 1;\n\n        a\n}");
+
+        buffer.set_mark(None);
+        buffer.seek_unchecked(Location::new(9, 3));
+        buffer.toggle_comment(Comment::Block("/*", "*/")).unwrap();
+        assert_eq!(buffer.get(3).unwrap(), "        /* a */");
+        assert_eq!(buffer.caret(), Location::new(12, 3));
+        buffer.toggle_comment(Comment::Block("/*", "*/")).unwrap();
+        assert_eq!(buffer.get(3).unwrap(), "        a");
+        assert_eq!(buffer.caret(), Location::new(9, 3));
+    }
+
+    #[test]
+    fn toggle_comment_restore() {
+        let text = "fn main() {\n    let a = 1;\n\n        a\n}";
+        let mut buffer = Buffer::new();
+        write!(buffer, "{text}").unwrap();
+        for comment in [Comment::Line("//"), Comment::Block("/*", "*/")] {
+            buffer.seek_unchecked(Location::new(0, 1));
+            buffer.set_mark(Some(Location::new(3, 3)));
+            let old = buffer.toggle_comment(comment).unwrap();
+            assert_ne!(buffer.to_string(), text);
+            // 用返回的各行替换被修改的各行即可撤销这次修改.
+            let end = Location::new(buffer.get(3).unwrap().len(), 3);
+            buffer.delete_range(Location::new(0, 1), end).unwrap();
+            buffer.seek_unchecked(Location::new(0, 1));
+            write!(buffer, "{old}").unwrap();
+            assert_eq!(buffer.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn matching_bracket() {
+        let mut buffer = Buffer::new();
+        write!(buffer, "fn f(a: [u8; 2]) {{\n    g(\")\");\n}}").unwrap();
+        assert_eq!(buffer.matching_bracket(Location::new(4, 0)), Some(Location::new(15, 0)));
+        assert_eq!(buffer.matching_bracket(Location::new(15, 0)), Some(Location::new(4, 0)));
+        assert_eq!(buffer.matching_bracket(Location::new(8, 0)), Some(Location::new(14, 0)));
+        assert_eq!(buffer.matching_bracket(Location::new(17, 0)), Some(Location::new(0, 2)));
+        assert_eq!(buffer.matching_bracket(Location::new(0, 2)), Some(Location::new(17, 0)));
+        assert_eq!(buffer.matching_bracket(Location::new(0, 0)), None);
+        // 不区分字符串中的括号.
+        assert_eq!(buffer.matching_bracket(Location::new(5, 1)), Some(Location::new(7, 1)));
+        assert_eq!(buffer.matching_bracket(Location::new(10, 1)), None);
+    }
+
+    #[test]
+    fn delete_range() {
+        let mut buffer = Buffer::new();
+        write!(buffer, "foo\nbar\nbaz").unwrap();
+        let (start, end) = (Location::new(1, 0), Location::new(2, 2));
+        assert_eq!(buffer.text_range(start, end).unwrap(), "oo\nbar\nba");
+        assert!(buffer.text_range(end, start).is_err());
+        assert_eq!(buffer.delete_range(start, end).unwrap(), "oo\nbar\nba");
+        assert_eq!("fz", format!("{}", buffer));
+        assert_eq!(buffer.caret(), start);
+    }
+
+    #[test]
+    fn cursors_and_anchors() {
+        let mut buffer = Buffer::new();
+        write!(buffer, "foo bar\nfoo_baz\nfoo").unwrap();
+        assert!(buffer.add_cursor(Cursor::new(Location::new(1, 1), None)));
+        assert!(!buffer.add_cursor(Cursor::new(Location::new(1, 1), None)));
+        assert!(!buffer.add_cursor(Cursor::new(buffer.caret(), None)));
+        assert_eq!(buffer.take_cursors().len(), 1);
+        assert!(buffer.cursors().is_empty());
+
+        // 修改位置之前的文本之后, anchor 仍然指向原来的文本.
+        let anchor = buffer.anchor(Location::new(4, 1));
+        buffer.seek_unchecked(Location::new(1, 0));
+        write!(buffer, "xx\ny").unwrap();
+        assert_eq!(buffer.resolve_anchor(anchor), Location::new(4, 2));
+        assert_eq!(buffer.get(2).unwrap()[4..], *"baz");
+
+        assert_eq!(buffer.find("foo", Location::new(1, 0)), Some((Location::new(0, 2), Location::new(3, 2))));
+        assert_eq!(buffer.find("foo", Location::new(3, 3)), Some((Location::new(0, 2), Location::new(3, 2))));
+        assert_eq!(buffer.find("baz\nf", Location::new(0, 0)), Some((Location::new(4, 2), Location::new(1, 3))));
+        assert_eq!(buffer.find("qux", Location::new(0, 0)), None);
+        assert_eq!(buffer.word_at(Location::new(2, 2)), Some((Location::new(0, 2), Location::new(7, 2))));
+        assert_eq!(buffer.word_at(Location::new(3, 3)), Some((Location::new(0, 3), Location::new(3, 3))));
+        assert_eq!(buffer.word_at(Location::new(0, 1)), Some((Location::new(0, 1), Location::new(3, 1))));
+    }
+}
@@ -0,0 +1,356 @@
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+use crate::editor::editarea::CaretMove;
+use crate::error;
+
+/// 编辑器中可以被按键绑定触发的命令.
+///
+/// 按键和命令的对应关系见 [`Keymap`](crate::editor::keymap::Keymap).
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Command {
+    /// 退出编辑器, 有未保存的修改时先询问是否保存.
+    Quit,
+    /// 不检查未保存的修改, 直接退出编辑器.
+    ForceQuit,
+    /// 保存当前 buffer 到其对应的文件, 带参数时保存到参数指定的文件.
+    Save,
+    /// 打开参数指定的文件进行编辑, 当前 buffer 有未保存的修改时先询问是否保存.
+    Open,
+    /// 跳转到参数指定的位置, 格式见 [`GotoTarget`](crate::editor::editarea::GotoTarget),
+    /// 没有参数时打开跳转位置的输入提示.
+    Goto,
+    /// 修改编辑器设置, 参数格式为 `name=value`.
+    Set,
+    /// 打开命令面板, 在状态栏中输入命令和参数.
+    Palette,
+    /// 移动 caret, 见 [`CaretMove`].
+    MoveCaret(CaretMove),
+    /// 把显示区域向下滚动一行, caret 只在离开显示区域时移动.
+    ScrollDown,
+    /// 把显示区域向上滚动一行, caret 只在离开显示区域时移动.
+    ScrollUp,
+    /// 把显示区域向下滚动半页.
+    ScrollHalfPageDown,
+    /// 把显示区域向上滚动半页.
+    ScrollHalfPageUp,
+    /// 滚动显示区域使 caret 所在的行位于显示区域的中间.
+    CenterCaret,
+    /// 扩展矩形选区, 只支持上下左右的移动, 见 [`EditArea::block_select`](crate::editor::editarea::EditArea::block_select).
+    BlockSelect(CaretMove),
+    /// 在 caret 处插入换行.
+    InsertNewline,
+    /// 在 caret 处插入缩进.
+    InsertTab,
+    /// 删除 caret 前的一个字符.
+    DeleteBackward,
+    /// 删除 caret 处的一个字符.
+    DeleteForward,
+    /// 删除 caret 前的一个单词.
+    DeleteWordBackward,
+    /// 删除 caret 后的一个单词.
+    DeleteWordForward,
+    /// 删除 caret 所在的行.
+    DeleteLine,
+    /// 复制 caret 所在的行到下一行.
+    DuplicateLine,
+    /// 把 caret 所在的行和上一行交换.
+    MoveLineUp,
+    /// 把 caret 所在的行和下一行交换.
+    MoveLineDown,
+    /// 把下一行连接到 caret 所在的行末尾.
+    JoinLines,
+    /// 切换 caret 所在行或者选区所在的各行的注释, 注释语法由文件类型决定.
+    ToggleComment,
+    /// 删除从 caret 到行末的内容, 如果 caret 已经在行末, 那么删除换行符, 删除的内容进入 kill ring.
+    KillLine,
+    /// 删除 mark 和 caret 之间的内容, 删除的内容进入 kill ring.
+    KillRegion,
+    /// 复制 mark 和 caret 之间的内容到 kill ring.
+    CopyRegion,
+    /// 在 caret 处插入 kill ring 中最近的内容.
+    Yank,
+    /// 在 caret 处设置 mark.
+    SetMark,
+    /// 在最上方的光标的上一行添加光标.
+    AddCursorAbove,
+    /// 在最下方的光标的下一行添加光标.
+    AddCursorBelow,
+    /// 选中 caret 处的单词, 或者在下一个与选区内容相同的文本处添加光标.
+    AddNextOccurrence,
+    /// 把跨越多行的选区拆分为每行一个光标.
+    SplitSelectionIntoLines,
+    /// 查看状态栏中显示过的消息的历史, 按下任意键返回.
+    Messages,
+    /// 取消当前的 mark, 多余的光标, 前缀参数, 未完成的按键序列和状态栏中的消息.
+    Cancel,
+    /// 为下一条命令设置重复次数 (universal argument).
+    UniversalArgument,
+}
+
+impl Command {
+    /// 命令是否会连续地向 kill ring 中追加内容, 连续的 kill 命令会合并到 kill ring 的同一项中.
+    pub fn is_kill(&self) -> bool {
+        matches!(self, Command::KillLine | Command::KillRegion | Command::CopyRegion)
+    }
+
+    /// 命令是否支持多光标, 执行其他命令之前会只保留主光标.
+    pub fn keeps_cursors(&self) -> bool {
+        matches!(
+            self,
+            Command::Quit | Command::ForceQuit | Command::Save | Command::Set | Command::Palette
+                | Command::MoveCaret(
+                    CaretMove::Left | CaretMove::Right | CaretMove::Up | CaretMove::Down | CaretMove::NextWord
+                        | CaretMove::PrevWord | CaretMove::LineStart | CaretMove::LineEnd
+                )
+                | Command::InsertNewline | Command::InsertTab | Command::DeleteBackward | Command::DeleteForward
+                | Command::DeleteWordBackward | Command::DeleteWordForward | Command::SetMark
+                | Command::AddCursorAbove | Command::AddCursorBelow | Command::AddNextOccurrence
+                | Command::SplitSelectionIntoLines | Command::Messages | Command::UniversalArgument
+        )
+    }
+
+    /// 命令是否支持矩形选区, 执行其他命令之前会取消矩形选区.
+    pub fn keeps_block(&self) -> bool {
+        matches!(
+            self,
+            Command::Quit | Command::ForceQuit | Command::Save | Command::Set | Command::Palette
+                | Command::BlockSelect(_) | Command::DeleteBackward | Command::DeleteForward
+                | Command::KillRegion | Command::CopyRegion | Command::Messages | Command::UniversalArgument
+        )
+    }
+
+    /// 命令是否会修改文本, 只读模式下不能执行这些命令.
+    pub fn is_editing(&self) -> bool {
+        matches!(
+            self,
+            Command::InsertNewline | Command::InsertTab | Command::DeleteBackward | Command::DeleteForward
+                | Command::DeleteWordBackward | Command::DeleteWordForward | Command::DeleteLine
+                | Command::DuplicateLine | Command::MoveLineUp | Command::MoveLineDown | Command::JoinLines
+                | Command::ToggleComment | Command::KillLine | Command::KillRegion | Command::Yank
+        )
+    }
+
+    /// 命令是否会写入文件, 只读模式下即使文本没有被修改也不能执行这些命令,
+    /// 因为保存时可能改变文件的编码和换行符.
+    pub fn writes_file(&self) -> bool {
+        matches!(self, Command::Save)
+    }
+}
+
+/// 所有命令及其名称, 名称用于配置文件中的按键绑定.
+pub const COMMANDS: &[(&str, Command)] = &[
+    ("quit", Command::Quit),
+    ("force-quit", Command::ForceQuit),
+    ("save", Command::Save),
+    ("open", Command::Open),
+    ("goto", Command::Goto),
+    ("set", Command::Set),
+    ("command-palette", Command::Palette),
+    ("move-up", Command::MoveCaret(CaretMove::Up)),
+    ("move-down", Command::MoveCaret(CaretMove::Down)),
+    ("move-left", Command::MoveCaret(CaretMove::Left)),
+    ("move-right", Command::MoveCaret(CaretMove::Right)),
+    ("next-word", Command::MoveCaret(CaretMove::NextWord)),
+    ("prev-word", Command::MoveCaret(CaretMove::PrevWord)),
+    ("line-start", Command::MoveCaret(CaretMove::LineStart)),
+    ("line-end", Command::MoveCaret(CaretMove::LineEnd)),
+    ("page-up", Command::MoveCaret(CaretMove::PageUp)),
+    ("page-down", Command::MoveCaret(CaretMove::PageDown)),
+    ("buffer-start", Command::MoveCaret(CaretMove::GlobalStart)),
+    ("buffer-end", Command::MoveCaret(CaretMove::GlobalEnd)),
+    ("prev-trace", Command::MoveCaret(CaretMove::PrevTrace)),
+    ("next-trace", Command::MoveCaret(CaretMove::NextTrace)),
+    ("matching-bracket", Command::MoveCaret(CaretMove::MatchingBracket)),
+    ("scroll-down", Command::ScrollDown),
+    ("scroll-up", Command::ScrollUp),
+    ("scroll-half-page-down", Command::ScrollHalfPageDown),
+    ("scroll-half-page-up", Command::ScrollHalfPageUp),
+    ("center-caret", Command::CenterCaret),
+    ("block-select-up", Command::BlockSelect(CaretMove::Up)),
+    ("block-select-down", Command::BlockSelect(CaretMove::Down)),
+    ("block-select-left", Command::BlockSelect(CaretMove::Left)),
+    ("block-select-right", Command::BlockSelect(CaretMove::Right)),
+    ("insert-newline", Command::InsertNewline),
+    ("insert-tab", Command::InsertTab),
+    ("delete-backward", Command::DeleteBackward),
+    ("delete-forward", Command::DeleteForward),
+    ("delete-word-backward", Command::DeleteWordBackward),
+    ("delete-word-forward", Command::DeleteWordForward),
+    ("delete-line", Command::DeleteLine),
+    ("duplicate-line", Command::DuplicateLine),
+    ("move-line-up", Command::MoveLineUp),
+    ("move-line-down", Command::MoveLineDown),
+    ("join-lines", Command::JoinLines),
+    ("toggle-comment", Command::ToggleComment),
+    ("kill-line", Command::KillLine),
+    ("kill-region", Command::KillRegion),
+    ("copy-region", Command::CopyRegion),
+    ("yank", Command::Yank),
+    ("set-mark", Command::SetMark),
+    ("add-cursor-above", Command::AddCursorAbove),
+    ("add-cursor-below", Command::AddCursorBelow),
+    ("add-next-occurrence", Command::AddNextOccurrence),
+    ("split-selection-into-lines", Command::SplitSelectionIntoLines),
+    ("messages", Command::Messages),
+    ("cancel", Command::Cancel),
+    ("universal-argument", Command::UniversalArgument),
+];
+
+/// 命令面板中可以使用的命令简写.
+pub const ALIASES: &[(&str, Command)] = &[
+    ("q", Command::Quit),
+    ("q!", Command::ForceQuit),
+    ("w", Command::Save),
+    ("e", Command::Open),
+];
+
+impl Command {
+    /// 命令是否接受参数, 其他命令的参数会被作为重复次数.
+    pub fn takes_argument(&self) -> bool {
+        matches!(self, Command::Save | Command::Open | Command::Goto | Command::Set)
+    }
+
+    /// 命令的名称, 见 [`COMMANDS`].
+    pub fn name(&self) -> &'static str {
+        COMMANDS.iter()
+            .find(|(_, command)| command == self)
+            .map(|(name, _)| *name)
+            .unwrap() // COMMANDS 包含了所有的命令.
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Command {
+    type Err = error::Error;
+
+    /// 通过名称查找命令.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::UnknownCommand`]: 没有此名称的命令, 比如名称拼写错误.
+    fn from_str(s: &str) -> error::Result<Command> {
+        COMMANDS.iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, command)| *command)
+            .ok_or_else(|| error::Error::UnknownCommand(s.to_owned()))
+    }
+}
+
+/// 解析命令面板中输入的命令行, 格式为 `命令名称 [参数]`, 命令名称可以使用 [`ALIASES`] 中的简写,
+/// 开头可以带有一个 `:`.
+///
+/// # Returns
+///
+/// 命令和去除了首尾空白的参数, 没有参数时为 None.
+///
+/// # Errors
+///
+/// - [`error::Error::UnknownCommand`]: 没有此名称的命令, 或者命令行为空.
+pub fn parse_command_line(line: &str) -> error::Result<(Command, Option<&str>)> {
+    let line = line.trim();
+    let line = line.strip_prefix(':').unwrap_or(line).trim_start();
+    let (name, argument) = match line.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, Some(argument.trim())),
+        None => (line, None),
+    };
+    let command = ALIASES.iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, command)| Ok(*command))
+        .unwrap_or_else(|| name.parse())?;
+    Ok((command, argument.filter(|argument| !argument.is_empty())))
+}
+
+/// 命令面板的补全, 返回补全之后完整的命令行的候选项.
+///
+/// - 输入命令名称时: 补全命令名称和简写.
+/// - 输入 [`Command::Save`] 和 [`Command::Open`] 的参数时: 补全文件路径.
+/// - 输入 [`Command::Set`] 的参数时: 补全 `options` 中的设置项名称.
+pub fn complete_command_line(line: &str, options: &[&str]) -> Vec<String> {
+    let Some((name, argument)) = line.split_once(' ') else {
+        let mut names: Vec<String> = ALIASES.iter().map(|(name, _)| name)
+            .chain(COMMANDS.iter().map(|(name, _)| name))
+            .filter(|name| name.starts_with(line))
+            .map(|name| name.to_string())
+            .collect();
+        names.sort();
+        return names;
+    };
+    let Ok((command, _)) = parse_command_line(name) else {
+        return Vec::new();
+    };
+    let arguments = match command {
+        Command::Save | Command::Open => complete_path(argument),
+        Command::Set => options.iter()
+            .filter(|option| option.starts_with(argument))
+            .map(|option| format!("{option}="))
+            .collect(),
+        _ => Vec::new(),
+    };
+    arguments.into_iter().map(|argument| format!("{name} {argument}")).collect()
+}
+
+/// 补全文件路径, 目录会以 `/` 结尾.
+fn complete_path(prefix: &str) -> Vec<String> {
+    let (dir, file_prefix) = match prefix.rfind('/') {
+        Some(idx) => prefix.split_at(idx + 1),
+        None => ("", prefix),
+    };
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<String> = entries.filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(file_prefix) {
+                return None;
+            }
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            Some(format!("{dir}{name}{}", if is_dir { "/" } else { "" }))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::command::{complete_command_line, parse_command_line, Command, COMMANDS};
+    use crate::editor::editarea::CaretMove;
+
+    #[test]
+    fn names_round_trip() {
+        for (name, command) in COMMANDS {
+            assert_eq!(command.name(), *name);
+            assert_eq!(name.parse::<Command>().unwrap(), *command);
+        }
+        assert!("no-such-command".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn command_line() {
+        assert_eq!(parse_command_line(":w  out.txt ").unwrap(), (Command::Save, Some("out.txt")));
+        assert_eq!(parse_command_line("q!").unwrap(), (Command::ForceQuit, None));
+        assert_eq!(parse_command_line("goto 120").unwrap(), (Command::Goto, Some("120")));
+        assert_eq!(
+            parse_command_line("move-down 3").unwrap(),
+            (Command::MoveCaret(CaretMove::Down), Some("3"))
+        );
+        assert!(parse_command_line(":").is_err());
+        assert!(parse_command_line("wq").is_err());
+    }
+
+    #[test]
+    fn completion() {
+        assert_eq!(complete_command_line("kill-", &[]), vec!["kill-line", "kill-region"]);
+        assert_eq!(complete_command_line("q", &[]), vec!["q", "q!", "quit"]);
+        assert_eq!(complete_command_line("set tab", &["tabwidth"]), vec!["set tabwidth="]);
+        assert_eq!(complete_command_line("e src/ma", &[]), vec!["e src/main.rs"]);
+        assert_eq!(complete_command_line("goto 1", &[]), Vec::<String>::new());
+    }
+}
@@ -1,23 +1,21 @@
-use std::fmt;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{cmp, fmt};
+use std::ops::Range;
+use std::fmt::Write;
+use std::str::FromStr;
 use std::io;
+use unicode_width::UnicodeWidthChar;
 use crate::{error, CharsCount};
-use crate::editor::buffer::Buffer;
+use crate::editor::buffer::{Buffer, Cursor};
 use crate::editor::Printable;
+use crate::editor::settings::{IndentStyle, Settings};
 use crate::editor::terminal::{Location, Size, Terminal};
+use crate::editor::word::word_starts;
 
-/// caret 上下移动时, 显示区域发生滚动会尽可能不会让 caret 直接贴住可显示范围的边缘, 而是保留一定的可视行数预览后/前几行.
-/// 此变量用于设置要保留 caret 与画面在竖直上的距离的行数.
-///
-/// # 特殊情况
-///
-/// - 如果可显示范围的高度不足 `2 * VERTICAL_PADDING`, 那么此参数无效, 页面滚动将按照.
-/// - 如果文本内容高度大于显示区域高度, 在 caret 即将到达文本底部时, 文本的末尾行最多上升到显示区域的最后一行,
-/// 而不是继续向上产生显示区域的空白行.
-const VERTICAL_PADDING: usize = 3;
+/// 跳转历史最多保存的位置数量.
+const TRACE_CAPACITY: usize = 100;
 
-/// caret 移动时与水平边缘的距离, 基本同理于 [`VERTICAL_PADDING`].
-const HORIZONTAL_PADDING: usize = 5;
+/// 自动补全的括号和引号, 见 [`Settings::auto_close`].
+const AUTO_CLOSE_PAIRS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\''), ('`', '`')];
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 /// caret 的各种移动方式.
@@ -51,7 +49,6 @@ pub enum CaretMove {
     /// # Notice
     ///
     /// `跳转` 不包括行内的 caret 移动.
-    /// todo 实现 (但暂时不会).
     PrevTrace,
     /// caret 移动到跳转后的位置.
     ///
@@ -59,43 +56,52 @@ pub enum CaretMove {
     ///
     /// `跳转` 不包括行内的 caret 移动.
     NextTrace,
+    /// caret 移动到与 caret 旁边的括号匹配的括号处, 会记录到跳转历史中.
+    MatchingBracket,
 }
 
-impl TryFrom<&KeyEvent> for CaretMove {
-    type Error = ();
-
-    fn try_from(value: &KeyEvent) -> Result<Self, Self::Error> {
-        let modifiers = value.modifiers;
-        Ok(match value.code {
-            KeyCode::Left if modifiers == KeyModifiers::NONE => CaretMove::Left,
-            KeyCode::Right if modifiers == KeyModifiers::NONE => CaretMove::Right,
-            KeyCode::Up if modifiers == KeyModifiers::NONE => CaretMove::Up,
-            KeyCode::Down if modifiers == KeyModifiers::NONE => CaretMove::Down,
-
-            KeyCode::Left if modifiers == KeyModifiers::CONTROL => CaretMove::PrevWord,
-            KeyCode::Right if modifiers == KeyModifiers::CONTROL => CaretMove::NextWord,
-
-            KeyCode::Left if modifiers == KeyModifiers::CONTROL | KeyModifiers::ALT => CaretMove::PrevTrace,
-            KeyCode::Right if modifiers == KeyModifiers::CONTROL | KeyModifiers::ALT => CaretMove::NextTrace,
-
-            KeyCode::Home if modifiers == KeyModifiers::NONE => CaretMove::LineStart,
-            KeyCode::End if modifiers == KeyModifiers::NONE => CaretMove::LineEnd,
-
-            KeyCode::Home if modifiers == KeyModifiers::CONTROL => CaretMove::GlobalStart,
-            KeyCode::End if modifiers == KeyModifiers::CONTROL => CaretMove::GlobalEnd,
-
-            KeyCode::PageUp => CaretMove::PageUp,
-            KeyCode::PageDown => CaretMove::PageDown,
-            _ => { Err(())? }
-        })
-    }
+/// 跳转的目标位置, 见 [`EditArea::goto`].
+///
+/// 文本格式:
+/// - `line` 或 `line:column`: 行号和列号, 都从 1 开始, 列号以字符为单位.
+/// - `+N` 或 `-N`: 相对当前行向下或向上 N 行.
+/// - `N%`: 文本中的百分比位置.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum GotoTarget {
+    /// 行号和可选的列号.
+    Line(usize, Option<usize>),
+    /// 相对当前行的行数偏移.
+    Relative(isize),
+    /// 文本中的百分比位置, 范围 0 ~ 100.
+    Percent(usize),
 }
 
-impl TryFrom<KeyEvent> for CaretMove {
-    type Error = ();
+impl FromStr for GotoTarget {
+    type Err = error::Error;
 
-    fn try_from(value: KeyEvent) -> Result<Self, Self::Error> {
-        (&value).try_into()
+    /// # Errors
+    ///
+    /// - [`error::Error::InvalidGotoTarget`]
+    fn from_str(s: &str) -> error::Result<GotoTarget> {
+        let invalid = || error::Error::InvalidGotoTarget(s.to_owned());
+        let s = s.trim();
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent: usize = percent.parse().map_err(|_| invalid())?;
+            return if percent <= 100 { Ok(GotoTarget::Percent(percent)) } else { Err(invalid()) };
+        }
+        if s.starts_with(['+', '-']) {
+            return s.parse().map(GotoTarget::Relative).map_err(|_| invalid());
+        }
+        let (line, column) = match s.split_once(':') {
+            Some((line, column)) => (line, Some(column.parse().map_err(|_| invalid())?)),
+            None => (s, None),
+        };
+        let line = line.parse().map_err(|_| invalid())?;
+        if line == 0 || column == Some(0) {
+            Err(invalid())
+        } else {
+            Ok(GotoTarget::Line(line, column))
+        }
     }
 }
 
@@ -147,6 +153,21 @@ impl Area {
     pub fn center(&self) -> Location {
         Location::new(self.width() / 2 + self.x(), self.height() / 2 + self.y())
     }
+
+    /// 终端中的坐标是否在区域内.
+    pub fn contains(&self, location: Location) -> bool {
+        (self.x()..self.x() + self.width()).contains(&location.x)
+            && (self.y()..self.y() + self.height()).contains(&location.y)
+    }
+}
+
+/// 矩形选区, 其中位置的 x 为显示列而不是字节位置, 见 [`EditArea::block_select`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+struct Block {
+    /// 开始选择时 caret 所在的行和显示列.
+    anchor: Location,
+    /// 选区另一个角的显示列, 这个角位于 caret 所在的行, 可以超出此行的宽度.
+    column: usize,
 }
 
 pub struct EditArea {
@@ -158,6 +179,18 @@ pub struct EditArea {
     welcome_buffer: Buffer,
     /// 标志画面是否需要重绘到终端上.
     need_printing: bool,
+    /// 跳转历史, 见 [`CaretMove::PrevTrace`] 和 [`CaretMove::NextTrace`].
+    trace: Vec<Location>,
+    /// 当前在跳转历史中的位置, 等于 `trace.len()` 时表示不在回溯跳转历史.
+    trace_index: usize,
+    /// 滚动边距, 行号, 折行和配色等显示相关的设置.
+    settings: Settings,
+    /// 当前的矩形选区.
+    block: Option<Block>,
+    /// 连续的竖直移动中 caret 期望所在的显示列, 经过较短的行时 caret 被限制到行末, 之后的行中恢复到此列.
+    ///
+    /// 其他的 caret 移动和编辑都会清除此列.
+    goal_column: Option<usize>,
 }
 
 impl Printable for EditArea {
@@ -175,12 +208,126 @@ impl Printable for EditArea {
 }
 
 impl EditArea {
-    /// 把 buffer 的 caret 坐标转换成 cursor 坐标.
-    fn get_cursor(&self) -> Location {
+    /// 把 buffer 的 caret 坐标转换成 cursor 在终端中的坐标.
+    pub fn get_cursor(&self) -> Location {
+        let caret = self.buffer.caret();
+        let text_area = self.text_area();
+        let column = self.caret_column();
+        let (offset_x, offset_y) = if self.settings.wrap {
+            let (row, column) = self.wrapped_position(caret);
+            let rows_before = (self.buffer_display_offset.y..caret.y)
+                .map(|y| self.wrapped_rows(y))
+                .sum::<usize>();
+            (column, rows_before + row)
+        } else {
+            (column.saturating_sub(self.buffer_display_offset.x), caret.y.saturating_sub(self.buffer_display_offset.y))
+        };
+        Location::new(
+            text_area.x() + offset_x.min(text_area.width()),
+            text_area.y() + offset_y.min(text_area.height()),
+        )
+    }
+
+    /// caret 在行中的显示列, 即 caret 之前的内容的显示宽度, 见 [`display_column`].
+    ///
+    /// 有矩形选区时为选区在 caret 所在行的角的显示列, 这个列可以超出行的宽度.
+    pub fn caret_column(&self) -> usize {
+        if let Some(block) = self.block {
+            return block.column;
+        }
         let caret = self.buffer.caret();
-        let offset_x = caret.x.saturating_sub(self.buffer_display_offset.x).min(self.display_area.width());
-        let offset_y = caret.y.saturating_sub(self.buffer_display_offset.y).min(self.display_area.height());
-        Location::new(offset_x, offset_y)
+        self.buffer.get(caret.y).map_or(0, |line| display_column(line, caret.x, self.settings.tab_width))
+    }
+
+    /// 第 y 行的显示文本, 见 [`expand_tabs`].
+    fn display_line(&self, y: usize) -> Option<String> {
+        self.buffer.get(y).map(|line| expand_tabs(line, self.settings.tab_width))
+    }
+
+    /// 行号栏的宽度, 包括行号和文本之间的一个空格, 不显示行号时为 0.
+    fn gutter_width(&self) -> usize {
+        if !self.settings.line_numbers {
+            return 0;
+        }
+        let width = self.buffer.lines_num().max(1).ilog10() as usize + 2;
+        // 显示区域太窄时不显示行号.
+        if width * 2 > self.display_area.width() { 0 } else { width }
+    }
+
+    /// 显示文本内容的区域, 即显示区域除去行号栏的部分.
+    fn text_area(&self) -> Area {
+        let gutter_width = self.gutter_width();
+        Area::new(
+            self.display_area.x() + gutter_width,
+            self.display_area.y(),
+            self.display_area.width() - gutter_width,
+            self.display_area.height(),
+        )
+    }
+
+    /// 文本区域的高度, 即显示的行数.
+    pub fn text_height(&self) -> usize {
+        self.text_area().height()
+    }
+
+    /// 折行显示时, 第 y 行占据的显示行数.
+    fn wrapped_rows(&self, y: usize) -> usize {
+        let width = self.text_area().width();
+        self.display_line(y).map_or(1, |line| wrap_line(&line, width).len())
+    }
+
+    /// buffer 中的位置在终端中的坐标, 不在显示区域内时返回 None.
+    fn screen_position(&self, location: Location) -> Option<Location> {
+        let line = self.buffer.get(location.y)?;
+        self.column_screen_position(location.y, display_column(line, location.x, self.settings.tab_width))
+    }
+
+    /// 第 y 行中显示列 column 在终端中的坐标, 不在显示区域内时返回 None.
+    fn column_screen_position(&self, y: usize, column: usize) -> Option<Location> {
+        let offset = self.buffer_display_offset;
+        let (column, row) = if self.settings.wrap {
+            let (row, column) = self.wrapped_column_position(y, column);
+            let rows_before = (offset.y..y).map(|y| self.wrapped_rows(y)).sum::<usize>();
+            (column, y.checked_sub(offset.y).map(|_| rows_before + row)?)
+        } else {
+            (column.checked_sub(offset.x)?, y.checked_sub(offset.y)?)
+        };
+        let text_area = self.text_area();
+        (column < text_area.width() && row < text_area.height())
+            .then(|| Location::new(text_area.x() + column, text_area.y() + row))
+    }
+
+    /// 折行显示时, buffer 中的位置在其所在行占据的显示行中的行和列.
+    ///
+    /// 位置在行末且行末恰好占满一个显示行时, 位置在这个显示行的末尾, 而不是下一个显示行的开头.
+    fn wrapped_position(&self, location: Location) -> (usize, usize) {
+        let column = self.buffer.get(location.y)
+            .map_or(0, |line| display_column(line, location.x, self.settings.tab_width));
+        self.wrapped_column_position(location.y, column)
+    }
+
+    /// 折行显示时, 第 y 行中显示列 column 在这一行占据的显示行中的行和列, 见 [`EditArea::wrapped_position`].
+    fn wrapped_column_position(&self, y: usize, column: usize) -> (usize, usize) {
+        let line = self.display_line(y).unwrap_or_default();
+        let rows = wrap_line(&line, self.text_area().width());
+        // 每个显示行的起始列递增, 第一个显示行从 0 开始.
+        let row = rows.iter().rposition(|(start, _)| *start <= column).unwrap_or(0);
+        (row, column - rows[row].0)
+    }
+
+    /// 修改设置, 见 [`Settings`].
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+        if settings.wrap {
+            self.buffer_display_offset.x = 0;
+        }
+        self.update_display_offset();
+        self.set_need_printing();
+    }
+
+    /// 显示区域, 见 [`EditArea::configure_area`].
+    pub fn display_area(&self) -> Area {
+        self.display_area
     }
 
     /// 更改显示区域的大小, 在 [`EditArea::print_to`] 和 [`EditArea::print_to_center`] 之前需要调用以确保正确显示.
@@ -204,31 +351,143 @@ impl EditArea {
     ///     - `Err(Error)`: 打印尺寸不符合要求或者 io 错误.
     pub fn print_to(&self, terminal: &mut Terminal) -> io::Result<()> {
         terminal.hide_cursor()?;
-        for row in 0..self.display_area.height() {
-            // 清空在显示区域内的内容.
-            terminal.move_cursor_to(Location::new(self.display_area.x(), self.display_area.y() + row))?;
-            terminal.print(" ".repeat(self.display_area.width()))?;
-
-            terminal.move_cursor_to(Location::new(self.display_area.x(), self.display_area.y() + row))?;
-            match self.buffer.get(row + self.buffer_display_offset.y) {
-                Some(line) => {
-                    let len = self.display_area.width()
-                        // 这里 line.chars_count() 可能小于 offset.x, 因为视角移动到了太右侧.
-                        .min(line.chars_count().saturating_sub(self.buffer_display_offset.x));
-                    // .min(line.width_cjk() - self.buffer_display_offset.x) // todo 测试 unicode width 是否准确, 多拿中文测.
-                    if len > 0 {
-                        terminal.print(&line[
-                            self.buffer_display_offset.x
-                                ..(self.buffer_display_offset.x + len)
-                            ])?;
-                    }
+        let text_area = self.text_area();
+        let mut y = self.buffer_display_offset.y;
+        let mut row = 0;
+        while row < self.display_area.height() {
+            let line = self.display_line(y);
+            // 折行显示时一行文本分为多个显示行打印.
+            let segments: Vec<String> = match &line {
+                Some(line) if self.settings.wrap => {
+                    wrap_line(line, text_area.width()).into_iter().map(|(_, segment)| segment).collect()
                 }
-                None => {}
+                // 这里 line 的宽度可能小于 offset.x, 因为视角移动到了太右侧.
+                Some(line) => vec![slice_columns(line, self.buffer_display_offset.x, text_area.width())],
+                None => vec![String::new()],
             };
+            for (idx, segment) in segments.iter().enumerate() {
+                if row >= self.display_area.height() {
+                    break;
+                }
+                // 清空在显示区域内的内容.
+                terminal.move_cursor_to(Location::new(self.display_area.x(), self.display_area.y() + row))?;
+                terminal.print(" ".repeat(self.display_area.width()))?;
+                terminal.move_cursor_to(Location::new(self.display_area.x(), self.display_area.y() + row))?;
+                if text_area.x() > self.display_area.x() {
+                    // 只在一行的第一个显示行显示行号.
+                    let number = if idx == 0 && line.is_some() { (y + 1).to_string() } else { String::new() };
+                    let gutter = format!("{number:>width$} ", width = text_area.x() - self.display_area.x() - 1);
+                    terminal.print_styled(gutter, self.settings.theme.line_number())?;
+                }
+                terminal.print(segment)?;
+                row += 1;
+            }
+            y += 1;
+        }
+        if let Some(brackets) = self.matching_brackets() {
+            for location in <[Location; 2]>::from(brackets) {
+                let Some(position) = self.screen_position(location) else {
+                    continue;
+                };
+                let bracket = self.buffer.get(location.y).and_then(|line| line[location.x..].chars().next());
+                terminal.move_cursor_to(position)?;
+                terminal.print_styled(bracket.unwrap_or_default(), self.settings.theme.matching_bracket())?;
+            }
+        }
+        self.print_block_to(terminal)?;
+        // 终端只有一个 cursor, 其他光标的 caret 处的字符使用单独的样式显示.
+        for cursor in self.buffer.cursors() {
+            let Some(position) = self.screen_position(cursor.caret) else {
+                continue;
+            };
+            let c = self.buffer.get(cursor.caret.y)
+                .and_then(|line| line[cursor.caret.x..].chars().next())
+                .filter(|c| *c != '\t')
+                .unwrap_or(' ');
+            terminal.move_cursor_to(position)?;
+            terminal.print_styled(c, self.settings.theme.secondary_cursor())?;
+        }
+        // 通过鼠标滚轮滚动之后 caret 可能不在显示区域内, 此时不显示 cursor.
+        if self.caret_visible() {
+            terminal.move_cursor_to(self.get_cursor())?;
+            terminal.show_cursor()?;
+        }
+        Ok(())
+    }
+
+    /// caret 所在的显示行是否在显示区域内.
+    fn caret_visible(&self) -> bool {
+        let caret = self.buffer.caret();
+        let offset = self.buffer_display_offset;
+        if caret.y < offset.y {
+            return false;
+        }
+        let row = if self.settings.wrap {
+            (offset.y..caret.y).map(|y| self.wrapped_rows(y)).sum::<usize>() + self.wrapped_position(caret).0
+        } else {
+            caret.y - offset.y
+        };
+        row < self.text_area().height()
+    }
+
+    /// 终端中的坐标对应的 buffer 中的位置, 是 [`EditArea::column_screen_position`] 的逆运算.
+    ///
+    /// 坐标在文本区域之外时使用文本区域内最近的坐标, 比如行号栏中的坐标对应行首,
+    /// 最后一行之后的坐标对应最后一行.
+    pub fn location_at(&self, screen: Location) -> Location {
+        let text_area = self.text_area();
+        let row = screen.y.saturating_sub(text_area.y()).min(text_area.height().saturating_sub(1));
+        let column = screen.x.saturating_sub(text_area.x()).min(text_area.width());
+        let offset = self.buffer_display_offset;
+        let last = self.buffer.lines_num().saturating_sub(1);
+        let (y, column) = if self.settings.wrap {
+            let mut y = offset.y;
+            let mut row = row;
+            while y < last && row >= self.wrapped_rows(y) {
+                row -= self.wrapped_rows(y);
+                y += 1;
+            }
+            let line = self.display_line(y).unwrap_or_default();
+            let rows = wrap_line(&line, text_area.width());
+            let row = row.min(rows.len() - 1);
+            // 点击在显示行末尾之后时, 位置在这个显示行的最后一个字符之前, 而不是下一个显示行的开头.
+            let end = rows.get(row + 1).map_or(usize::MAX, |(start, _)| start.saturating_sub(1));
+            (y, (rows[row].0 + column).min(end))
+        } else {
+            ((offset.y + row).min(last), offset.x + column)
+        };
+        let x = self.buffer.get(y).map_or(0, |line| byte_at_column(line, column, self.settings.tab_width));
+        Location::new(x, y)
+    }
+
+    /// 高亮矩形选区, 选区宽度为 0 时在 caret 所在行之外的各行显示光标.
+    fn print_block_to(&self, terminal: &mut Terminal) -> io::Result<()> {
+        let Some((lines, columns)) = self.block_ranges() else {
+            return Ok(());
+        };
+        let caret = self.buffer.caret();
+        let zero_width = columns.is_empty();
+        let (columns, style) = if zero_width {
+            (columns.start..columns.start + 1, self.settings.theme.secondary_cursor())
+        } else {
+            (columns, self.settings.theme.selection())
+        };
+        for y in lines {
+            // caret 所在行使用终端的 cursor.
+            if zero_width && y == caret.y {
+                continue;
+            }
+            let cells = self.display_line(y).map(|line| display_cells(&line)).unwrap_or_default();
+            for column in columns.clone() {
+                let cell = cells.get(column).copied().unwrap_or(Some(' '));
+                // 宽字符占据的后续列不单独打印.
+                let (Some(c), Some(position)) = (cell, self.column_screen_position(y, column)) else {
+                    continue;
+                };
+                terminal.move_cursor_to(position)?;
+                terminal.print_styled(c, style)?;
+            }
         }
-        let Location { x: offset_x, y: offset_y } = self.get_cursor();
-        terminal.move_cursor_to(Location::new(self.display_area.x() + offset_x, self.display_area.y() + offset_y))?;
-        terminal.show_cursor()?;
         Ok(())
     }
 
@@ -240,7 +499,7 @@ impl EditArea {
     /// - [`error::Error::BufferSizeExceeds`]: welcome_buffer 的横向长度或者纵向长度超过了可打印范围.
     pub fn print_welcome_to(&self, terminal: &mut Terminal) -> error::Result<()> {
         let buffer_size = self.welcome_buffer.size();
-        if !(self.display_area.size() > self.welcome_buffer.size()) { // 偏序比较.
+        if self.display_area.size().partial_cmp(&buffer_size) != Some(cmp::Ordering::Greater) { // 偏序比较.
             return Err(error::Error::BufferSizeExceeds {
                 buffer_size,
                 area_size: self.display_area.size(),
@@ -273,9 +532,29 @@ impl EditArea {
             buffer: Buffer::new(),
             welcome_buffer: Buffer::new(),
             need_printing: false,
+            trace: Vec::new(),
+            trace_index: 0,
+            settings: Settings::default(),
+            block: None,
+            goal_column: None,
         }
     }
 
+    /// 替换正在编辑的 buffer, 比如打开了其他文件, 跳转历史会被清空.
+    pub fn set_buffer(&mut self, buffer: Buffer) {
+        self.buffer = buffer;
+        self.block = None;
+        self.buffer_display_offset = Location::new(0, 0);
+        self.trace.clear();
+        self.trace_index = 0;
+        self.update_display_offset();
+        self.set_need_printing();
+    }
+
+    pub(crate) fn get_buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
     pub(crate) fn get_buffer_mut(&mut self) -> &mut Buffer {
         &mut self.buffer
     }
@@ -293,15 +572,21 @@ impl EditArea {
     /// 返回 offset 是否发生变化, 即画面是否需要改变.
     pub fn update_display_offset(&mut self) -> bool {
         let raw_offset = self.buffer_display_offset;
+        if self.settings.wrap {
+            self.update_wrapped_display_offset();
+            return self.buffer_display_offset != raw_offset;
+        }
         let caret = self.buffer.caret();
+        let text_area = self.text_area();
+        let vertical_padding = self.settings.vertical_padding;
         // 检测 caret 是否在竖直方向移动较大.
-        let v_padding = if self.display_area.height() >= 2 * VERTICAL_PADDING { VERTICAL_PADDING } else { 0 };
+        let v_padding = if text_area.height() >= 2 * vertical_padding { vertical_padding } else { 0 };
         let y_display = caret.y as isize - self.buffer_display_offset.y as isize; // caret 在显示区域的 y 坐标.
-        if y_display >= (self.display_area.height() as isize - v_padding as isize) {
+        if y_display >= (text_area.height() as isize - v_padding as isize) {
             // 向下较多.
             let bottom = (caret.y + v_padding)
                 .min(self.buffer.lines_num() /*让最后一行最高上升到最底边(只在文本高高度大于显示区域的时候)*/);
-            self.buffer_display_offset.y = bottom.saturating_sub(self.display_area.height());
+            self.buffer_display_offset.y = bottom.saturating_sub(text_area.height());
         } else if y_display < v_padding as isize {
             // 向上较多.
             if caret.y >= v_padding {
@@ -312,28 +597,56 @@ impl EditArea {
         }
         // 竖直方向的补充检查: 如果文本高度大于显示高度, 但是最后一行浮空(高于显示区域最后一行)了, 就让文本最后一行贴底.
         // 此检查针对用户拉高终端的操作.
-        if self.buffer.lines_num() > self.display_area.height() {
+        if self.buffer.lines_num() > text_area.height() {
             // 最后一行之后一行在显示区域的竖直方向从第一行开始的偏移量.
             let bottom_offset_from_display = self.buffer.lines_num() - self.buffer_display_offset.y;
             // 如果浮空了就贴底, 通过 saturating_sub 暗含了和 0 的比较.
-            self.buffer_display_offset.y -= self.display_area.height().saturating_sub(bottom_offset_from_display);
+            self.buffer_display_offset.y -= text_area.height().saturating_sub(bottom_offset_from_display);
         }
         // 检测 caret 是否在水平方向移动较大. 
-        let h_padding = if self.display_area.width() >= 2 * HORIZONTAL_PADDING { HORIZONTAL_PADDING } else { 0 };
-        let x_display = caret.x as isize - self.buffer_display_offset.x as isize; // caret 在显示区域的 x 坐标.
+        let column = self.caret_column();
+        let horizontal_padding = self.settings.horizontal_padding;
+        let h_padding = if text_area.width() >= 2 * horizontal_padding { horizontal_padding } else { 0 };
+        let x_display = column as isize - self.buffer_display_offset.x as isize; // caret 在显示区域的 x 坐标.
         if x_display < h_padding as isize {
-            if caret.x < h_padding {
+            if column < h_padding {
                 self.buffer_display_offset.x = 0;
             } else {
-                self.buffer_display_offset.x = caret.x - h_padding;
+                self.buffer_display_offset.x = column - h_padding;
             }
-        } else if x_display > (self.display_area.width() - h_padding) as isize {
-            let right = caret.x + h_padding;
+        } else if x_display > (text_area.width() - h_padding) as isize {
+            let right = column + h_padding;
             // 这里不需要行末贴边, 让用户感知到这行后面是空的.
-            self.buffer_display_offset.x = right.saturating_sub(self.display_area.width());
+            self.buffer_display_offset.x = right.saturating_sub(text_area.width());
         }
         self.buffer_display_offset != raw_offset
     }
+
+    /// 折行显示时更新 buffer_display_offset, 此时 offset.y 为显示区域第一行所在的文本行, offset.x 始终为 0.
+    ///
+    /// 保证 caret 所在的显示行与显示区域上下边缘之间保留 [`Settings::vertical_padding`] 行.
+    fn update_wrapped_display_offset(&mut self) {
+        let caret = self.buffer.caret();
+        let height = self.text_area().height();
+        let vertical_padding = self.settings.vertical_padding;
+        let v_padding = if height >= 2 * vertical_padding { vertical_padding } else { 0 };
+        let (caret_row, _) = self.wrapped_position(caret);
+        // 从第 from 行开头到 caret 所在显示行的显示行数.
+        let rows_before = |edit_area: &EditArea, from: usize| -> usize {
+            (from..caret.y).map(|y| edit_area.wrapped_rows(y)).sum::<usize>() + caret_row
+        };
+        // 每行至少占据一个显示行, 所以 caret 之前超过 height 行的内容一定不会显示.
+        let offset = &mut self.buffer_display_offset;
+        offset.x = 0;
+        offset.y = offset.y.clamp(caret.y.saturating_sub(height), caret.y);
+        while self.buffer_display_offset.y > 0 && rows_before(self, self.buffer_display_offset.y) < v_padding {
+            self.buffer_display_offset.y -= 1;
+        }
+        while self.buffer_display_offset.y < caret.y
+            && rows_before(self, self.buffer_display_offset.y) >= height.saturating_sub(v_padding) {
+            self.buffer_display_offset.y += 1;
+        }
+    }
 }
 
 impl EditArea {
@@ -343,7 +656,7 @@ impl EditArea {
             if caret.y > 0 {
                 match self.buffer.get(caret.y - 1) {
                     Some(line) => {
-                        caret.x = line.chars_count(); // 移动到行末, 也就是最后一个字符的后面.
+                        caret.x = line.len(); // 移动到行末, 也就是最后一个字符的后面.
                         caret.y -= 1;
                     }
                     None => {
@@ -353,7 +666,8 @@ impl EditArea {
                 }
             }
         } else {
-            caret.x -= 1;
+            let line = self.buffer.get_current_line().unwrap();
+            caret.x -= line[..caret.x].chars().next_back().map_or(0, char::len_utf8);
         }
         self.move_caret_to(caret).unwrap()
     }
@@ -368,7 +682,7 @@ impl EditArea {
                 caret.y = self.buffer.lines_num();
             }
             Some(line) => {
-                if caret.x == line.chars_count() {
+                if caret.x == line.len() {
                     // 到了行末.
                     if self.buffer.get(caret.y + 1).is_some() {
                         // 下一行有内容.
@@ -376,41 +690,37 @@ impl EditArea {
                         caret.y += 1;
                     }
                 } else {
-                    caret.x += 1;
+                    caret.x += line[caret.x..].chars().next().map_or(0, char::len_utf8);
                 }
             }
         }
         self.move_caret_to(caret).unwrap()
     }
 
+    /// 竖直移动时 caret 应该移动到的显示列, 即连续的竖直移动开始时 caret 所在的显示列.
+    fn vertical_move_column(&self) -> usize {
+        self.goal_column.unwrap_or_else(|| self.caret_column())
+    }
+
+    /// 竖直移动 caret 到第 y 行的 column 列, 并记住此列作为之后的竖直移动的期望列.
+    fn move_caret_vertically(&mut self, y: usize, column: usize) -> Location {
+        let x = self.buffer.get(y).map_or(0, |line| byte_at_column(line, column, self.settings.tab_width));
+        let cursor = self.move_caret_to(Location::new(x, y)).unwrap();
+        self.goal_column = Some(column);
+        cursor
+    }
+
     fn move_caret_up(&mut self) -> Location {
-        // todo 添加对非 ascii 字符宽度的字符的支持, 比如适配中文的宽度.
-        let mut caret = self.buffer.caret();
-        if caret.y != 0 {
-            let prev_line = self.buffer.get(caret.y - 1);
-            match prev_line {
-                Some(line) => {
-                    caret.y -= 1;
-                    caret.x = caret.x.min(line.chars_count());
-                }
-                None => { /* 可能是没有内容, 不变化 y 值.*/ }
-            }
-            // caret.y == 0 不用考虑, 因为是向上.
-        }
-        self.move_caret_to(caret).unwrap()
+        let caret = self.buffer.caret();
+        // caret.y == 0 时不变化 y 值.
+        let y = caret.y.saturating_sub(1);
+        self.move_caret_vertically(y, self.vertical_move_column())
     }
 
     fn move_caret_down(&mut self) -> Location {
-        let mut caret = self.buffer.caret();
-        let next_line = self.buffer.get(caret.y + 1);
-        match next_line {
-            Some(line) => {
-                caret.y += 1;
-                caret.x = caret.x.min(line.chars_count());
-            }
-            None => {}
-        }
-        self.move_caret_to(caret).unwrap()
+        let caret = self.buffer.caret();
+        let y = if self.buffer.get(caret.y + 1).is_some() { caret.y + 1 } else { caret.y };
+        self.move_caret_vertically(y, self.vertical_move_column())
     }
 
     fn move_caret_to_global_end(&mut self) -> Location {
@@ -429,33 +739,31 @@ impl EditArea {
         self.move_caret_to(Location::new(0, 0)).unwrap()
     }
 
+    /// 移动 caret 到下一个单词的开始, 单词的划分见 [`word_starts`], 之后没有单词时移动到文本末尾.
     fn move_caret_to_next_word(&mut self) -> Location {
-        let mut reader = self.buffer.get_reader().unwrap();
-        let ok = reader.skip_until_blank().is_ok() && reader.skip_until_not_blank().is_ok();
-        if ok {
-            self.move_caret_to(reader.caret()).unwrap()
-        } else {
-            self.move_caret_to_global_end()
+        let caret = self.buffer.caret();
+        let next = (caret.y..self.buffer.lines_num()).find_map(|y| {
+            let starts = word_starts(self.buffer.get(y)?, self.settings.subword);
+            let x = starts.into_iter().find(|x| y > caret.y || *x > caret.x)?;
+            Some(Location::new(x, y))
+        });
+        match next {
+            Some(location) => self.move_caret_to(location).unwrap(),
+            None => self.move_caret_to_global_end(),
         }
     }
 
+    /// 移动 caret 到上一个单词的开始, caret 在单词中间时移动到这个单词的开始, 之前没有单词时移动到文本开头.
     fn move_caret_to_prev_word(&mut self) -> Location {
-        let mut reader = self.buffer.get_reader().unwrap();
-        let ok = match reader.peek() {
-            Some(current_char) if !current_char.is_whitespace() => {
-                reader.back_until_blank().is_ok()
-                    && reader.back_until_not_blank().is_ok()
-                    && reader.back_until_blank().is_ok()
-            }
-            Some(_) | None => { // None 表示 caret 在 buffer 末尾.
-                reader.back_until_not_blank().is_ok()
-                    && reader.back_until_blank().is_ok()
-            }
-        };
-        if ok {
-            self.move_caret_to(reader.caret()).unwrap()
-        } else {
-            self.move_caret_to_global_start()
+        let caret = self.buffer.caret();
+        let prev = (0..=caret.y).rev().find_map(|y| {
+            let starts = word_starts(self.buffer.get(y)?, self.settings.subword);
+            let x = starts.into_iter().rev().find(|x| y < caret.y || *x < caret.x)?;
+            Some(Location::new(x, y))
+        });
+        match prev {
+            Some(location) => self.move_caret_to(location).unwrap(),
+            None => self.move_caret_to_global_start(),
         }
     }
 
@@ -473,17 +781,13 @@ impl EditArea {
     }
 
     fn move_caret_page_up(&mut self) -> Location {
-        let mut caret = self.buffer.caret();
-        caret.y = caret.y.saturating_sub(self.display_area.height());
-        caret.x = 0;
-        self.move_caret_to(caret).unwrap()
+        let y = self.buffer.caret().y.saturating_sub(self.display_area.height());
+        self.move_caret_vertically(y, self.vertical_move_column())
     }
 
     fn move_caret_page_down(&mut self) -> Location {
-        let mut caret = self.buffer.caret();
-        caret.x = 0;
-        caret.y = (caret.y + self.display_area.height()).min(self.buffer.lines_num() - 1);
-        self.move_caret_to(caret).unwrap()
+        let y = (self.buffer.caret().y + self.display_area.height()).min(self.buffer.lines_num().saturating_sub(1));
+        self.move_caret_vertically(y, self.vertical_move_column())
     }
 
     /// 移动 caret, 会根据 display_area 协调  buffer_display_offset 以使 buffer
@@ -500,14 +804,42 @@ impl EditArea {
     pub fn move_caret_to(&mut self, caret: Location) -> error::Result<Location> {
         // 检测 caret 移动的位置是否合理.
         self.buffer.check_caret(caret)?;
+        self.goal_column = None;
+        let brackets = self.matching_brackets();
         self.buffer.seek_unchecked(caret);
-        if self.update_display_offset() {
+        // 显示偏移和括号的高亮改变时都需要重绘.
+        if self.update_display_offset() || self.matching_brackets() != brackets {
             self.set_need_printing();
         }
         // 通过返回 caret 在屏幕中的位置来通知调用者对 cursor 进行更新而无需绘制其他的内容.
         Ok(self.get_cursor())
     }
 
+    /// caret 旁边的括号和与之匹配的括号的位置, 见 [`Buffer::matching_bracket`].
+    ///
+    /// caret 处的括号优先于 caret 之前的括号.
+    fn matching_brackets(&self) -> Option<(Location, Location)> {
+        let caret = self.buffer.caret();
+        if let Some(matching) = self.buffer.matching_bracket(caret) {
+            return Some((caret, matching));
+        }
+        let line = self.buffer.get(caret.y)?;
+        let (x, _) = line.get(..caret.x)?.char_indices().next_back()?;
+        let before = Location::new(x, caret.y);
+        self.buffer.matching_bracket(before).map(|matching| (before, matching))
+    }
+
+    /// 移动 caret 到与 caret 旁边的括号匹配的括号处, 并记录到跳转历史中.
+    fn move_caret_to_matching_bracket(&mut self) -> Location {
+        match self.matching_brackets() {
+            Some((_, matching)) => {
+                self.record_jump();
+                self.move_caret_to(matching).unwrap()
+            }
+            None => self.get_cursor(),
+        }
+    }
+
     /// 对 caret 执行特定的移动操作.
     /// 具体操作见 [`CaretMove`].
     ///
@@ -522,34 +854,1236 @@ impl EditArea {
             CaretMove::Down => self.move_caret_down(),
             CaretMove::NextWord => self.move_caret_to_next_word(),
             CaretMove::PrevWord => self.move_caret_to_prev_word(),
-            CaretMove::GlobalEnd => self.move_caret_to_global_end(),
-            CaretMove::GlobalStart => self.move_caret_to_global_start(),
+            CaretMove::GlobalEnd => {
+                self.record_jump();
+                self.move_caret_to_global_end()
+            }
+            CaretMove::GlobalStart => {
+                self.record_jump();
+                self.move_caret_to_global_start()
+            }
             CaretMove::LineEnd => self.move_caret_to_line_end(),
             CaretMove::LineStart => self.move_caret_to_line_start(),
             CaretMove::PageUp => self.move_caret_page_up(),
             CaretMove::PageDown => self.move_caret_page_down(),
-            _ => {
-                todo!("{:?}.", caret_move)
-            }
+            CaretMove::PrevTrace => self.move_caret_to_prev_trace(),
+            CaretMove::NextTrace => self.move_caret_to_next_trace(),
+            CaretMove::MatchingBracket => self.move_caret_to_matching_bracket(),
         } // CaretOutOfRange 在这里不会出现, 因为都是计算好了的坐标移动.
     }
+
+    /// 把当前 caret 位置记录到跳转历史中, 在跳转之前调用.
+    ///
+    /// 如果正在回溯跳转历史, 那么当前位置之后的历史会被丢弃.
+    fn record_jump(&mut self) {
+        self.trace.truncate(self.trace_index);
+        self.trace.push(self.buffer.caret());
+        if self.trace.len() > TRACE_CAPACITY {
+            self.trace.remove(0);
+        }
+        self.trace_index = self.trace.len();
+    }
+
+    fn move_caret_to_prev_trace(&mut self) -> Location {
+        if self.trace_index == 0 {
+            return self.get_cursor();
+        }
+        if self.trace_index == self.trace.len() {
+            // 记录回溯开始时的位置, 以便通过 NextTrace 回到这里.
+            self.trace.push(self.buffer.caret());
+        }
+        self.trace_index -= 1;
+        let caret = self.buffer.clamp_caret(self.trace[self.trace_index]);
+        self.move_caret_to(caret).unwrap()
+    }
+
+    fn move_caret_to_next_trace(&mut self) -> Location {
+        if self.trace_index + 1 >= self.trace.len() {
+            return self.get_cursor();
+        }
+        self.trace_index += 1;
+        let caret = self.buffer.clamp_caret(self.trace[self.trace_index]);
+        self.move_caret_to(caret).unwrap()
+    }
+
+    /// 计算跳转目标在 buffer 中的位置, 不检查位置是否有效.
+    fn resolve_goto(&self, target: GotoTarget) -> Location {
+        let caret = self.buffer.caret();
+        let last_line = self.buffer.lines_num() - 1;
+        let (y, column) = match target {
+            GotoTarget::Line(line, column) => (line - 1, column.unwrap_or(1) - 1),
+            GotoTarget::Relative(offset) => (caret.y.saturating_add_signed(offset).min(last_line), 0),
+            GotoTarget::Percent(percent) => ((self.buffer.lines_num() * percent).div_ceil(100).max(1) - 1, 0),
+        };
+        let x = match self.buffer.get(y) {
+            // 把字符为单位的列号转换为字节位置, 超出行末时保留超出的部分以便报错.
+            Some(line) => match line.char_indices().map(|(idx, _)| idx).chain([line.len()]).nth(column) {
+                Some(x) => x,
+                None => line.len() + column - line.chars_count(),
+            },
+            None => 0,
+        };
+        Location::new(x, y)
+    }
+
+    /// 跳转到指定的位置, 并记录到跳转历史中.
+    ///
+    /// 相对跳转和百分比跳转会被限制在文本范围内, 指定的行号和列号超出文本范围时返回错误.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::CaretOutOfHeight`]: 行号超出文本范围, 即大于总行数.
+    /// - [`error::Error::CaretOutOfLen`]: 列号超出行的范围, 即大于行的字符数 + 1.
+    ///
+    /// # Returns
+    ///
+    /// - 跳转后 cursor 在屏幕中的坐标.
+    pub fn goto(&mut self, target: GotoTarget) -> error::Result<Location> {
+        let target = self.resolve_goto(target);
+        self.buffer.check_caret(target)?;
+        if target != self.buffer.caret() {
+            self.record_jump();
+        }
+        self.move_caret_to(target)
+    }
+
+    /// 移动到指定的位置, 超出文本范围的位置会被限制到文本范围内, 不记录到跳转历史中.
+    ///
+    /// 用于设置打开文件时的初始位置.
+    pub fn goto_clamped(&mut self, target: GotoTarget) -> Location {
+        let target = self.buffer.clamp_caret(self.resolve_goto(target));
+        self.move_caret_to(target).unwrap()
+    }
 }
 
 impl EditArea {
+    /// 是否有多个光标.
+    pub fn has_multiple_cursors(&self) -> bool {
+        !self.buffer.cursors().is_empty()
+    }
+
+    /// 只保留主光标.
+    pub fn clear_cursors(&mut self) {
+        if self.has_multiple_cursors() {
+            self.buffer.take_cursors();
+            self.set_need_printing();
+        }
+    }
+
+    /// 在每个光标处执行 f, f 中通过 buffer 的 caret 和 mark 操作当前的光标.
+    ///
+    /// 光标按照从后往前的顺序处理, 这样在一个光标处修改文本只会影响已经处理过的光标,
+    /// 已经处理过的光标的位置通过 [`Buffer::anchor`] 记录, 所有光标处理完之后再转换回位置.
+    /// 处理之后位置相同的光标会被合并.
+    pub fn for_each_cursor(&mut self, mut f: impl FnMut(&mut EditArea)) {
+        if !self.has_multiple_cursors() {
+            f(self);
+            return;
+        }
+        let primary = self.buffer.cursor();
+        let mut cursors: Vec<(bool, Cursor)> = self.buffer.take_cursors().into_iter().map(|c| (false, c)).collect();
+        cursors.push((true, primary));
+        cursors.sort_by_key(|(_, cursor)| cmp::Reverse(cursor.caret));
+        let offset = self.buffer_display_offset;
+        let mut anchors = Vec::with_capacity(cursors.len());
+        for (is_primary, cursor) in cursors {
+            // 各个光标的期望列不同, 多光标时不记住期望列.
+            self.goal_column = None;
+            self.buffer.set_cursor(cursor);
+            f(self);
+            let cursor = self.buffer.cursor();
+            anchors.push((is_primary, self.buffer.anchor(cursor.caret), cursor.mark.map(|mark| self.buffer.anchor(mark))));
+        }
+        let mut cursors: Vec<(bool, Cursor)> = anchors.into_iter()
+            .map(|(is_primary, caret, mark)| {
+                let cursor = Cursor::new(self.buffer.resolve_anchor(caret), mark.map(|mark| self.buffer.resolve_anchor(mark)));
+                (is_primary, cursor)
+            })
+            .collect();
+        // 恢复添加光标时的顺序, 保证之后添加的光标仍然在最后.
+        cursors.reverse();
+        let (_, primary) = cursors.iter().find(|(is_primary, _)| *is_primary).copied().unwrap();
+        self.buffer.set_cursor(primary);
+        for (_, cursor) in cursors {
+            self.buffer.add_cursor(cursor);
+        }
+        self.goal_column = None;
+        self.buffer_display_offset = offset;
+        self.update_display_offset();
+        self.set_need_printing();
+    }
+
+    /// 删除光标的选区并取消 mark.
+    ///
+    /// # Returns
+    ///
+    /// 是否删除了内容.
+    fn delete_selection(&mut self) -> bool {
+        let region = self.buffer.region();
+        self.unset_mark();
+        match region {
+            Some((start, end)) => self.delete_range(start, end).is_some(),
+            None => false,
+        }
+    }
+
+    /// 在每个光标处执行插入操作, 见 [`EditArea::for_each_cursor`].
+    ///
+    /// 有多个光标时, 插入的内容会替换各个光标的选区.
+    pub fn insert_at_cursors(&mut self, mut f: impl FnMut(&mut EditArea)) {
+        let replaces = self.has_multiple_cursors();
+        self.for_each_cursor(|edit_area| {
+            if replaces {
+                edit_area.delete_selection();
+            }
+            f(edit_area);
+        });
+    }
+
+    /// 在每个光标处执行删除操作, 见 [`EditArea::for_each_cursor`].
+    ///
+    /// 有多个光标时, 有选区的光标只删除选区.
+    pub fn delete_at_cursors(&mut self, mut f: impl FnMut(&mut EditArea)) {
+        let replaces = self.has_multiple_cursors();
+        self.for_each_cursor(|edit_area| {
+            if !(replaces && edit_area.delete_selection()) {
+                f(edit_area);
+            }
+        });
+    }
+
+    /// 在最上方的光标的上一行添加光标, 新光标和最上方的光标的显示列相同.
+    pub fn add_cursor_above(&mut self) {
+        self.add_cursor_vertically(true);
+    }
+
+    /// 在最下方的光标的下一行添加光标, 新光标和最下方的光标的显示列相同.
+    pub fn add_cursor_below(&mut self) {
+        self.add_cursor_vertically(false);
+    }
+
+    fn add_cursor_vertically(&mut self, up: bool) {
+        let carets = self.buffer.cursors().iter().map(|c| c.caret).chain([self.buffer.caret()]);
+        let caret = if up { carets.min() } else { carets.max() }.unwrap();
+        let y = if up { caret.y.checked_sub(1) } else { Some(caret.y + 1) };
+        let Some((y, line)) = y.and_then(|y| Some((y, self.buffer.get(y)?))) else {
+            return;
+        };
+        let column = self.buffer.get(caret.y).map_or(0, |line| display_column(line, caret.x, self.settings.tab_width));
+        let x = byte_at_column(line, column, self.settings.tab_width);
+        if self.buffer.add_cursor(Cursor::new(Location::new(x, y), None)) {
+            self.set_need_printing();
+        }
+    }
+
+    /// 主光标没有选区时, 选中 caret 处的单词; 否则在最后添加的光标之后查找与选区内容相同的文本,
+    /// 并在那里添加一个选中此文本的光标, 到达 buffer 末尾时从头继续查找.
+    pub fn add_next_occurrence(&mut self) {
+        let region = self.buffer.region().filter(|(start, end)| start != end);
+        let Some((start, end)) = region else {
+            if let Some((start, end)) = self.buffer.word_at(self.buffer.caret()) {
+                self.buffer.set_mark(Some(start));
+                self.move_caret_to(end).unwrap();
+                self.set_need_printing();
+            }
+            return;
+        };
+        let text = self.buffer.text_range(start, end).unwrap();
+        let last = self.buffer.cursors().last().copied().unwrap_or(self.buffer.cursor());
+        let from = last.mark.map_or(last.caret, |mark| mark.max(last.caret));
+        if let Some((start, end)) = self.buffer.find(&text, from) {
+            if self.buffer.add_cursor(Cursor::new(end, Some(start))) {
+                self.set_need_printing();
+            }
+        }
+    }
+
+    /// 把跨越多行的选区拆分为每行一个光标, 每个光标选中选区在这一行中的部分, caret 在选中部分的末尾.
+    ///
+    /// 选区在某一行的开头结束时, 这一行不添加光标.
+    pub fn split_selection_into_lines(&mut self) {
+        let Some((start, end)) = self.buffer.region().filter(|(start, end)| start.y < end.y) else {
+            return;
+        };
+        let last = if end.x == 0 { end.y - 1 } else { end.y };
+        for y in start.y..=last {
+            let mark = Location::new(if y == start.y { start.x } else { 0 }, y);
+            let caret = if y == end.y { end } else { Location::new(self.buffer.get(y).unwrap().len(), y) };
+            if y == start.y {
+                self.buffer.set_cursor(Cursor::new(caret, Some(mark)));
+            } else {
+                self.buffer.add_cursor(Cursor::new(caret, Some(mark)));
+            }
+        }
+        self.update_display_offset();
+        self.set_need_printing();
+    }
+
     pub fn del_char(&mut self) -> error::Result<char> {
+        self.goal_column = None;
         let rst = self.buffer.del_char();
         self.set_need_printing();
         self.update_display_offset();
         rst
     }
+
+    /// 使用 f 修改 buffer 的内容, 之后更新显示偏移并重绘, 用于 [`Buffer`] 中的各种编辑操作.
+    pub fn edit<T>(&mut self, f: impl FnOnce(&mut Buffer) -> T) -> T {
+        self.goal_column = None;
+        let rst = f(&mut self.buffer);
+        self.set_need_printing();
+        self.update_display_offset();
+        rst
+    }
+
+    /// 一级缩进的文本, 由 [`Settings::indent_style`] 和 [`Settings::indent_size`] 决定.
+    pub fn indent_unit(&self) -> String {
+        match self.settings.indent_style {
+            IndentStyle::Space => " ".repeat(self.settings.indent_size),
+            IndentStyle::Tab => "\t".to_owned(),
+        }
+    }
+
+    /// 在 caret 处换行, 新行继承当前行 caret 之前的缩进, caret 之后的空白会被删除.
+    ///
+    /// caret 之前的内容以 `indent_after` 中的字符结尾时, 新行增加一级缩进,
+    /// 如果此时 caret 之后是对应的右括号, 那么右括号会被移动到再下一行, 并保持当前行的缩进.
+    ///
+    /// * `indent_after`: 见 [`Filetype::indent_after`](crate::editor::filetype::Filetype::indent_after).
+    pub fn insert_newline(&mut self, indent_after: &[char]) {
+        self.goal_column = None;
+        let caret = self.buffer.caret();
+        let line = self.buffer.get_current_line().cloned().unwrap_or_default();
+        let (before, after) = line.split_at(caret.x);
+        let indent: String = before.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+        let blank_len = after.len() - after.trim_start_matches([' ', '\t']).len();
+        self.buffer.delete_range(caret, Location::new(caret.x + blank_len, caret.y)).unwrap();
+        let opening = before.trim_end().chars().next_back().filter(|c| indent_after.contains(c));
+        match opening {
+            Some(opening) => {
+                write!(self.buffer, "\n{indent}{}", self.indent_unit()).unwrap();
+                let closing = match opening {
+                    '{' => Some('}'),
+                    '[' => Some(']'),
+                    '(' => Some(')'),
+                    _ => None,
+                };
+                if closing.is_some_and(|closing| after.trim_start().starts_with(closing)) {
+                    let inner = self.buffer.caret();
+                    write!(self.buffer, "\n{indent}").unwrap();
+                    self.buffer.seek_unchecked(inner);
+                }
+            }
+            None => write!(self.buffer, "\n{indent}").unwrap(),
+        }
+        self.set_need_printing();
+        self.update_display_offset();
+    }
+
+    /// caret 前后的字符.
+    fn chars_around_caret(&self) -> (Option<char>, Option<char>) {
+        let caret = self.buffer.caret();
+        let Some(line) = self.buffer.get(caret.y) else {
+            return (None, None);
+        };
+        (line[..caret.x].chars().next_back(), line[caret.x..].chars().next())
+    }
+
+    /// 在 caret 处插入字符, 开启了 [`Settings::auto_close`] 时自动补全括号和引号:
+    ///
+    /// - 输入左括号或引号, 且 caret 之后为空白, 行末或者右括号时, 同时插入对应的右括号或引号, caret 位于两者之间.
+    ///   引号之前是字母或数字时不补全, 比如 `don't`.
+    /// - 输入的右括号或引号和 caret 处的字符相同时, caret 越过这个字符而不插入.
+    pub fn insert_char(&mut self, c: char) {
+        self.goal_column = None;
+        let (before, after) = self.chars_around_caret();
+        if self.settings.auto_close {
+            if after == Some(c) && AUTO_CLOSE_PAIRS.iter().any(|(_, close)| *close == c) {
+                self.move_caret(CaretMove::Right);
+                return;
+            }
+            let closes_after = after.is_none_or(|after| {
+                after.is_whitespace() || AUTO_CLOSE_PAIRS.iter().any(|(open, close)| open != close && *close == after)
+            });
+            if let Some(&(open, close)) = AUTO_CLOSE_PAIRS.iter().find(|(open, _)| *open == c) {
+                let in_word = open == close && before.is_some_and(char::is_alphanumeric);
+                if closes_after && !in_word {
+                    write!(self.buffer, "{open}{close}").unwrap();
+                    let caret = self.buffer.caret();
+                    self.buffer.seek_unchecked(Location::new(caret.x - close.len_utf8(), caret.y));
+                    self.set_need_printing();
+                    self.update_display_offset();
+                    return;
+                }
+            }
+        }
+        write!(self, "{c}").unwrap();
+    }
+
+    /// 删除 caret 前的一个字符.
+    ///
+    /// 使用空格缩进且 caret 之前只有空格时, 删除到上一个缩进位置, 即删除一级缩进.
+    /// 开启了 [`Settings::auto_close`] 且 caret 在一对空的括号或引号之间时, 同时删除这一对字符.
+    ///
+    /// # Errors
+    ///
+    /// 同 [`Buffer::del_char`].
+    pub fn delete_backward(&mut self) -> error::Result<()> {
+        let caret = self.buffer.caret();
+        let in_indent = self.buffer.get_current_line()
+            .and_then(|line| line.get(..caret.x))
+            .is_some_and(|before| !before.is_empty() && before.bytes().all(|b| b == b' '));
+        if self.settings.indent_style == IndentStyle::Space && in_indent {
+            let size = self.settings.indent_size;
+            self.delete_range(Location::new((caret.x - 1) / size * size, caret.y), caret);
+            return Ok(());
+        }
+        if let (Some(before), Some(after)) = self.chars_around_caret() {
+            if self.settings.auto_close && AUTO_CLOSE_PAIRS.contains(&(before, after)) {
+                let start = Location::new(caret.x - before.len_utf8(), caret.y);
+                self.delete_range(start, Location::new(caret.x + after.len_utf8(), caret.y));
+                return Ok(());
+            }
+        }
+        self.del_char().map(|_| ())
+    }
+
+    /// 在 caret 处设置 mark.
+    pub fn set_mark(&mut self) {
+        self.buffer.set_mark(Some(self.buffer.caret()));
+    }
+
+    /// 取消 mark.
+    pub fn unset_mark(&mut self) {
+        self.buffer.set_mark(None);
+    }
+
+    /// 删除从 caret 开始的 `count` 行内容.
+    ///
+    /// - `count` 为 1 时: 删除 caret 到行末的内容, 如果 caret 已经在行末, 那么删除行末的换行符.
+    /// - `count` 大于 1 时: 删除 caret 到之后第 `count` 行开头的内容 (包括换行符).
+    ///
+    /// # Returns
+    ///
+    /// 被删除的文本, 如果没有可删除的内容则返回 None.
+    pub fn kill_line(&mut self, count: usize) -> Option<String> {
+        let caret = self.buffer.caret();
+        let line_len = self.buffer.get_current_line()?.len();
+        let last_line = self.buffer.lines_num() - 1;
+        let end = if count <= 1 && caret.x < line_len {
+            Location::new(line_len, caret.y)
+        } else if caret.y + count.max(1) <= last_line {
+            Location::new(0, caret.y + count.max(1))
+        } else {
+            Location::new(self.buffer.get(last_line)?.len(), last_line)
+        };
+        self.delete_range(caret, end)
+    }
+
+    /// 删除 mark 和 caret 之间的内容, 并取消 mark.
+    ///
+    /// # Returns
+    ///
+    /// 被删除的文本, 如果没有设置 mark 则返回 None.
+    pub fn kill_region(&mut self) -> Option<String> {
+        let (start, end) = self.buffer.region()?;
+        self.unset_mark();
+        self.delete_range(start, end)
+    }
+
+    /// 获取 mark 和 caret 之间的内容, 并取消 mark.
+    pub fn copy_region(&mut self) -> Option<String> {
+        let (start, end) = self.buffer.region()?;
+        self.unset_mark();
+        self.buffer.text_range(start, end).ok()
+    }
+
+    fn delete_range(&mut self, start: Location, end: Location) -> Option<String> {
+        if start == end {
+            return None;
+        }
+        self.goal_column = None;
+        let removed = self.buffer.delete_range(start, end).ok();
+        self.set_need_printing();
+        self.update_display_offset();
+        removed
+    }
+}
+
+impl EditArea {
+    /// 处理鼠标点击, 坐标不在显示区域内时忽略.
+    ///
+    /// - `clicks` 为 1: 移动 caret 到点击的位置, 并取消 mark.
+    /// - `clicks` 为 2: 选中点击位置的单词.
+    /// - `clicks` 为 3: 选中点击位置所在的行, 包括行末的换行符.
+    pub fn click(&mut self, screen: Location, clicks: usize) {
+        if !self.display_area.contains(screen) {
+            return;
+        }
+        let location = self.location_at(screen);
+        self.unset_mark();
+        let (start, end) = match clicks {
+            1 => (location, location),
+            2 => self.buffer.word_at(location).unwrap_or((location, location)),
+            _ => {
+                let end = match self.buffer.get(location.y + 1) {
+                    Some(_) => Location::new(0, location.y + 1),
+                    None => Location::new(self.buffer.get(location.y).map_or(0, String::len), location.y),
+                };
+                (Location::new(0, location.y), end)
+            }
+        };
+        if start != end {
+            self.buffer.set_mark(Some(start));
+        }
+        self.move_caret_to(end).unwrap();
+        self.set_need_printing();
+    }
+
+    /// 处理鼠标拖动, 从 caret 开始选择到拖动的位置, 已经有选区时扩展选区.
+    ///
+    /// 拖动到显示区域之外时, 显示偏移会随 caret 移动.
+    pub fn drag_to(&mut self, screen: Location) {
+        let location = self.location_at(screen);
+        if self.buffer.region().is_none() {
+            self.set_mark();
+        }
+        self.move_caret_to(location).unwrap();
+        self.set_need_printing();
+    }
+
+    /// 把显示区域向下 (lines 为正) 或向上滚动 lines 行, caret 不移动.
+    ///
+    /// 最多滚动到最后一行位于显示区域的最后一行.
+    pub fn scroll(&mut self, lines: isize) {
+        let max = self.buffer.lines_num().saturating_sub(self.text_area().height());
+        let y = self.buffer_display_offset.y.saturating_add_signed(lines).min(max);
+        if y != self.buffer_display_offset.y {
+            self.buffer_display_offset.y = y;
+            self.set_need_printing();
+        }
+    }
+
+    /// 滚动显示区域, 同 [`EditArea::scroll`], 之后只在 caret 不在显示区域内 (包括滚动边距) 时移动 caret,
+    /// 移动时保持 caret 的显示列不变.
+    pub fn scroll_view(&mut self, lines: isize) {
+        self.scroll(lines);
+        let offset = self.buffer_display_offset;
+        let column = self.caret_column();
+        let mut y = self.buffer.caret().y;
+        // 从 caret 所在的行开始向显示区域内逐行查找, 直到 caret 在这一行时不需要调整显示偏移.
+        loop {
+            let x = self.buffer.get(y).map_or(0, |line| byte_at_column(line, column, self.settings.tab_width));
+            self.buffer.seek_unchecked(Location::new(x, y));
+            self.update_display_offset();
+            let adjusted = self.buffer_display_offset.y;
+            self.buffer_display_offset = offset;
+            match adjusted.cmp(&offset.y) {
+                cmp::Ordering::Equal => break,
+                cmp::Ordering::Less if y + 1 < self.buffer.lines_num() => y += 1,
+                cmp::Ordering::Greater if y > 0 => y -= 1,
+                _ => break,
+            }
+        }
+        self.update_display_offset();
+    }
+
+    /// 滚动显示区域使 caret 所在的显示行位于显示区域的中间, 最多滚动到最后一行位于显示区域的最后一行.
+    pub fn center_caret(&mut self) {
+        let caret = self.buffer.caret();
+        let text_area = self.text_area();
+        let rows_of = |y: usize| if self.settings.wrap { self.wrapped_rows(y) } else { 1 };
+        let mut rows = if self.settings.wrap { self.wrapped_position(caret).0 } else { 0 };
+        let mut y = caret.y;
+        while y > 0 && rows + rows_of(y - 1) <= text_area.height() / 2 {
+            y -= 1;
+            rows += rows_of(y);
+        }
+        let max = self.buffer.lines_num().saturating_sub(text_area.height());
+        self.buffer_display_offset.y = y.min(max);
+        self.set_need_printing();
+    }
+
+    /// 矩形选区包含的行和显示列的范围, 没有矩形选区时返回 None.
+    fn block_ranges(&self) -> Option<(Range<usize>, Range<usize>)> {
+        let block = self.block?;
+        let caret = self.buffer.caret();
+        let lines = block.anchor.y.min(caret.y)..block.anchor.y.max(caret.y) + 1;
+        let columns = block.anchor.x.min(block.column)..block.anchor.x.max(block.column);
+        Some((lines, columns))
+    }
+
+    /// 是否有矩形选区.
+    pub fn has_block(&self) -> bool {
+        self.block.is_some()
+    }
+
+    /// 取消矩形选区.
+    pub fn cancel_block(&mut self) {
+        if self.block.take().is_some() {
+            self.set_need_printing();
+        }
+    }
+
+    /// 扩展矩形选区, 没有矩形选区时以 caret 为起点开始选择.
+    ///
+    /// 只支持上下左右的移动, 左右移动时一次移动一个显示列, 并且可以超出行末.
+    ///
+    /// # Returns
+    ///
+    /// - 移动 caret 后, 屏幕 cursor 应该移动到的位置.
+    pub fn block_select(&mut self, caret_move: CaretMove) -> Location {
+        let caret = self.buffer.caret();
+        let column = self.caret_column();
+        let mut block = self.block.unwrap_or(Block { anchor: Location::new(column, caret.y), column });
+        let y = match caret_move {
+            CaretMove::Up => caret.y.saturating_sub(1),
+            CaretMove::Down => (caret.y + 1).min(self.buffer.lines_num().saturating_sub(1)),
+            CaretMove::Left => {
+                block.column = block.column.saturating_sub(1);
+                caret.y
+            }
+            CaretMove::Right => {
+                block.column += 1;
+                caret.y
+            }
+            _ => return self.get_cursor(),
+        };
+        self.block = Some(block);
+        self.move_block_caret(y)
+    }
+
+    /// 把 caret 移动到第 y 行中矩形选区的角所在的列.
+    fn move_block_caret(&mut self, y: usize) -> Location {
+        let column = self.block.map_or(0, |block| block.column);
+        let x = self.buffer.get(y).map_or(0, |line| byte_at_column(line, column, self.settings.tab_width));
+        // 直接修改行的内容之后 caret 可能已经无效.
+        self.buffer.seek_unchecked(self.buffer.clamp_caret(self.buffer.caret()));
+        self.set_need_printing();
+        self.move_caret_to(Location::new(x, y)).unwrap()
+    }
+
+    /// 把矩形选区的两个角都移动到显示列 column, 即宽度为 0 的矩形选区.
+    fn collapse_block(&mut self, column: usize) {
+        let Some(block) = self.block else {
+            return;
+        };
+        self.block = Some(Block { anchor: Location::new(column, block.anchor.y), column });
+        self.move_block_caret(self.buffer.caret().y);
+    }
+
+    /// 第 y 行中显示列范围 columns 对应的字节范围, 宽字符和制表符被部分覆盖时也包括在内.
+    fn column_byte_range(&self, y: usize, columns: &Range<usize>) -> Range<usize> {
+        let line = self.buffer.get(y).map_or("", String::as_str);
+        byte_at_column(line, columns.start, self.settings.tab_width)..byte_at_column(line, columns.end, self.settings.tab_width)
+    }
+
+    /// 删除各行中显示列范围 columns 内的文本, 宽度不够的行不受影响.
+    fn delete_columns(&mut self, lines: Range<usize>, columns: &Range<usize>) {
+        for y in lines {
+            let range = self.column_byte_range(y, columns);
+            if let Some(line) = self.buffer.get_mut(y) {
+                line.replace_range(range, "");
+            }
+        }
+    }
+
+    /// 用空格把第 y 行补齐到至少 column 的宽度.
+    ///
+    /// # Returns
+    ///
+    /// 显示列 column 对应的字节位置.
+    fn pad_line(&mut self, y: usize, column: usize) -> usize {
+        let tab_width = self.settings.tab_width;
+        let line = self.buffer.get_mut(y).unwrap();
+        let width = display_column(line, line.len(), tab_width);
+        line.extend(std::iter::repeat_n(' ', column.saturating_sub(width)));
+        byte_at_column(line, column, tab_width)
+    }
+
+    /// 矩形选区中各行被选中的文本, 行之间使用 `\n` 连接.
+    fn block_text(&self) -> Option<String> {
+        let (lines, columns) = self.block_ranges()?;
+        let text: Vec<&str> = lines
+            .map(|y| &self.buffer.get(y).map_or("", String::as_str)[self.column_byte_range(y, &columns)])
+            .collect();
+        Some(text.join("\n"))
+    }
+
+    /// 在矩形选区的每一行中插入字符, 宽度不够的行先用空格补齐, 矩形选区有宽度时先删除其中的文本.
+    ///
+    /// 插入之后矩形选区的宽度为 0 并位于插入的字符之后, 可以继续输入.
+    pub fn block_insert(&mut self, c: char) {
+        let Some((lines, columns)) = self.block_ranges() else {
+            return;
+        };
+        self.delete_columns(lines.clone(), &columns);
+        let mut column = columns.start;
+        for y in lines {
+            let x = self.pad_line(y, columns.start);
+            let line = self.buffer.get_mut(y).unwrap();
+            line.insert(x, c);
+            column = display_column(line, x + c.len_utf8(), self.settings.tab_width);
+        }
+        self.collapse_block(column);
+    }
+
+    /// 删除矩形选区中的文本, 矩形选区宽度为 0 时删除每一行中选区之前 (backward 为 true 时) 或者之后的一列.
+    pub fn block_delete(&mut self, backward: bool) {
+        let Some((lines, columns)) = self.block_ranges() else {
+            return;
+        };
+        let start = columns.start;
+        let (deleted, column) = if !columns.is_empty() {
+            (columns, start)
+        } else if backward && start > 0 {
+            (start - 1..start, start - 1)
+        } else if backward {
+            return;
+        } else {
+            (start..start + 1, start)
+        };
+        self.delete_columns(lines, &deleted);
+        self.collapse_block(column);
+    }
+
+    /// 获取矩形选区中的文本, 并取消矩形选区.
+    pub fn copy_block(&mut self) -> Option<String> {
+        let text = self.block_text();
+        self.cancel_block();
+        text
+    }
+
+    /// 删除矩形选区中的文本, 并取消矩形选区.
+    ///
+    /// # Returns
+    ///
+    /// 被删除的文本, 没有矩形选区时返回 None.
+    pub fn kill_block(&mut self) -> Option<String> {
+        let text = self.block_text()?;
+        let (lines, columns) = self.block_ranges()?;
+        self.delete_columns(lines, &columns);
+        self.collapse_block(columns.start);
+        self.cancel_block();
+        Some(text)
+    }
+
+    /// 把文本的各行作为矩形插入到 caret 所在的列以及之后的各行中,
+    /// 宽度不够的行先用空格补齐, 行数不够时在 buffer 末尾添加新行. caret 位置不变.
+    pub fn yank_block(&mut self, text: &str) {
+        self.goal_column = None;
+        let caret = self.buffer.caret();
+        let column = self.caret_column();
+        for (idx, part) in text.split('\n').enumerate() {
+            let y = caret.y + idx;
+            if y >= self.buffer.lines_num() {
+                self.buffer.seek_unchecked(self.buffer.end());
+                self.buffer.write_char('\n').unwrap();
+            }
+            let x = self.pad_line(y, column);
+            self.buffer.get_mut(y).unwrap().insert_str(x, part);
+        }
+        self.buffer.seek_unchecked(caret);
+        self.update_display_offset();
+        self.set_need_printing();
+    }
 }
 
 impl fmt::Write for EditArea {
     /// 在当前的 buffer caret 位置插入字符串.
     fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.goal_column = None;
         let rst = self.buffer.write_str(s);
         self.set_need_printing();
         self.update_display_offset();
         rst
     }
-}
\ No newline at end of file
+}
+
+/// 字符位于第 column 列时的显示宽度, 制表符延伸到下一个制表位, 控制字符的宽度为 0.
+fn char_width(c: char, column: usize, tab_width: usize) -> usize {
+    if c == '\t' {
+        tab_width - column % tab_width
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
+/// 把行中的制表符展开为到下一个制表位的空格, 得到用于显示的文本.
+fn expand_tabs(line: &str, tab_width: usize) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+    for c in line.chars() {
+        let width = char_width(c, column, tab_width);
+        if c == '\t' {
+            expanded.extend(std::iter::repeat_n(' ', width));
+        } else {
+            expanded.push(c);
+        }
+        column += width;
+    }
+    expanded
+}
+
+/// 行中字节位置 x 之前的内容的显示宽度, 考虑制表位和中文等宽字符.
+pub(crate) fn display_column(line: &str, x: usize, tab_width: usize) -> usize {
+    line[..x].chars().fold(0, |column, c| column + char_width(c, column, tab_width))
+}
+
+/// 显示列 column 对应的字节位置.
+///
+/// column 落在制表符或者宽字符中间时返回这个字符的开始位置, 超出行末时返回行末.
+fn byte_at_column(line: &str, column: usize, tab_width: usize) -> usize {
+    let mut current = 0;
+    for (idx, c) in line.char_indices() {
+        current += char_width(c, current, tab_width);
+        if current > column {
+            return idx;
+        }
+    }
+    line.len()
+}
+
+/// 显示文本中每一列的字符, 宽字符占据的后续列为 None.
+fn display_cells(line: &str) -> Vec<Option<char>> {
+    let mut cells = Vec::with_capacity(line.len());
+    for c in line.chars() {
+        let width = c.width().unwrap_or(0);
+        if width > 0 {
+            cells.push(Some(c));
+            cells.extend(std::iter::repeat_n(None, width - 1));
+        }
+    }
+    cells
+}
+
+/// 把显示文本按照宽度 width 分为多个显示行, 至少有一个显示行.
+///
+/// 放不下的宽字符会被移动到下一个显示行.
+///
+/// # Returns
+///
+/// 每个显示行的起始显示列和内容.
+fn wrap_line(line: &str, width: usize) -> Vec<(usize, String)> {
+    let width = width.max(1);
+    let mut rows = vec![(0, String::new())];
+    let mut row_width = 0;
+    let mut column = 0;
+    for c in line.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if row_width + char_width > width && row_width > 0 {
+            rows.push((column, String::new()));
+            row_width = 0;
+        }
+        rows.last_mut().unwrap().1.push(c);
+        row_width += char_width;
+        column += char_width;
+    }
+    rows
+}
+
+/// 显示文本中从第 start 列开始, 宽度为 width 的部分, 被截断的宽字符使用空格代替.
+fn slice_columns(line: &str, start: usize, width: usize) -> String {
+    let end = start + width;
+    let mut slice = String::new();
+    let mut column = 0;
+    for c in line.chars() {
+        let char_width = c.width().unwrap_or(0);
+        let next = column + char_width;
+        if next > end {
+            // 右边缘被截断的宽字符.
+            slice.extend(std::iter::repeat_n(' ', end.saturating_sub(column.max(start))));
+            break;
+        }
+        if column >= start {
+            slice.push(c);
+        } else if next > start {
+            // 左边缘被截断的宽字符.
+            slice.extend(std::iter::repeat_n(' ', next - start));
+        }
+        column = next;
+    }
+    slice
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+    use crate::editor::editarea::{expand_tabs, slice_columns, wrap_line, Area, CaretMove, EditArea, GotoTarget};
+    use crate::editor::settings::{IndentStyle, Settings};
+    use crate::editor::terminal::Location;
+
+    fn edit_area_with(text: &str) -> EditArea {
+        let mut edit_area = EditArea::new();
+        edit_area.configure_area(Area::new(0, 0, 40, 10));
+        write!(edit_area, "{}", text).unwrap();
+        edit_area.move_caret_to(Location::new(0, 0)).unwrap();
+        edit_area
+    }
+
+    #[test]
+    fn parse_goto_target() {
+        assert_eq!("12".parse::<GotoTarget>().unwrap(), GotoTarget::Line(12, None));
+        assert_eq!("12:5".parse::<GotoTarget>().unwrap(), GotoTarget::Line(12, Some(5)));
+        assert_eq!("+3".parse::<GotoTarget>().unwrap(), GotoTarget::Relative(3));
+        assert_eq!("-3".parse::<GotoTarget>().unwrap(), GotoTarget::Relative(-3));
+        assert_eq!("50%".parse::<GotoTarget>().unwrap(), GotoTarget::Percent(50));
+        for invalid in ["", "0", "1:0", "abc", "101%", "1:x", "+"] {
+            assert!(invalid.parse::<GotoTarget>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn goto_and_trace() {
+        let mut edit_area = edit_area_with("one\n二三四\nthree\nfour");
+        edit_area.goto(GotoTarget::Line(2, Some(3))).unwrap();
+        assert_eq!(edit_area.buffer.caret(), Location::new(6, 1));
+        assert!(edit_area.goto(GotoTarget::Line(5, None)).is_err());
+        assert!(edit_area.goto(GotoTarget::Line(1, Some(5))).is_err());
+        edit_area.goto(GotoTarget::Relative(10)).unwrap();
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 3));
+        edit_area.goto(GotoTarget::Percent(50)).unwrap();
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 1));
+
+        edit_area.move_caret(CaretMove::PrevTrace);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 3));
+        edit_area.move_caret(CaretMove::PrevTrace);
+        assert_eq!(edit_area.buffer.caret(), Location::new(6, 1));
+        edit_area.move_caret(CaretMove::PrevTrace);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 0));
+        edit_area.move_caret(CaretMove::PrevTrace);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 0));
+        edit_area.move_caret(CaretMove::NextTrace);
+        edit_area.move_caret(CaretMove::NextTrace);
+        edit_area.move_caret(CaretMove::NextTrace);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 1));
+        edit_area.move_caret(CaretMove::NextTrace);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 1));
+    }
+
+    #[test]
+    fn goto_clamped() {
+        let mut edit_area = edit_area_with("one\n二三四\nthree\nfour");
+        edit_area.goto_clamped(GotoTarget::Line(2, Some(9)));
+        assert_eq!(edit_area.buffer.caret(), Location::new(9, 1));
+        edit_area.goto_clamped(GotoTarget::Line(9, Some(2)));
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 3));
+        assert!(edit_area.trace.is_empty());
+    }
+
+    #[test]
+    fn tabs_and_wide_chars() {
+        assert_eq!(expand_tabs("\ta\tb", 4), "    a   b");
+        assert_eq!(expand_tabs("中\t", 4), "中  ");
+        assert_eq!(slice_columns("a中文b", 2, 3), " 文");
+        assert_eq!(slice_columns("a中文b", 1, 3), "中 ");
+        assert_eq!(wrap_line("ab中文", 3), vec![(0, "ab".to_owned()), (2, "中".to_owned()), (4, "文".to_owned())]);
+        assert_eq!(wrap_line("", 3), vec![(0, String::new())]);
+
+        let mut edit_area = edit_area_with("\tx\n中文ab\nabcdef");
+        edit_area.move_caret(CaretMove::Right);
+        assert_eq!(edit_area.buffer.caret(), Location::new(1, 0));
+        assert_eq!(edit_area.get_cursor(), Location::new(4, 0));
+        edit_area.move_caret(CaretMove::Down);
+        // 显示列 4 在 "中文" 之后.
+        assert_eq!(edit_area.buffer.caret(), Location::new(6, 1));
+        edit_area.move_caret(CaretMove::Left);
+        assert_eq!(edit_area.buffer.caret(), Location::new(3, 1));
+        assert_eq!(edit_area.get_cursor(), Location::new(2, 1));
+        edit_area.move_caret(CaretMove::Down);
+        assert_eq!(edit_area.buffer.caret(), Location::new(2, 2));
+        edit_area.move_caret(CaretMove::Up);
+        edit_area.move_caret(CaretMove::Up);
+        // 显示列 2 在制表符中间, 移动到制表符之前.
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 0));
+    }
+
+    #[test]
+    fn auto_indent() {
+        let mut edit_area = edit_area_with("fn main() {}");
+        edit_area.move_caret_to(Location::new(11, 0)).unwrap();
+        edit_area.insert_newline(&['{']);
+        assert_eq!(edit_area.buffer.to_string(), "fn main() {\n    \n}");
+        assert_eq!(edit_area.buffer.caret(), Location::new(4, 1));
+        write!(edit_area, "let a = 1;").unwrap();
+        edit_area.insert_newline(&['{']);
+        assert_eq!(edit_area.buffer.caret(), Location::new(4, 2));
+
+        // 删除一级缩进.
+        write!(edit_area, "  ").unwrap();
+        edit_area.delete_backward().unwrap();
+        assert_eq!(edit_area.buffer.caret(), Location::new(4, 2));
+        edit_area.delete_backward().unwrap();
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 2));
+        edit_area.delete_backward().unwrap();
+        assert_eq!(edit_area.buffer.to_string(), "fn main() {\n    let a = 1;\n}");
+
+        let mut edit_area = edit_area_with("if a:  b");
+        edit_area.set_settings(Settings { indent_style: IndentStyle::Tab, ..Settings::default() });
+        edit_area.move_caret_to(Location::new(5, 0)).unwrap();
+        edit_area.insert_newline(&[':']);
+        assert_eq!(edit_area.buffer.to_string(), "if a:\n\tb");
+        edit_area.insert_newline(&[':']);
+        assert_eq!(edit_area.buffer.to_string(), "if a:\n\t\n\tb");
+    }
+
+    #[test]
+    fn brackets() {
+        let mut edit_area = edit_area_with("f(a[0]) {\n}");
+        edit_area.move_caret_to(Location::new(1, 0)).unwrap();
+        assert_eq!(edit_area.matching_brackets(), Some((Location::new(1, 0), Location::new(6, 0))));
+        edit_area.move_caret(CaretMove::MatchingBracket);
+        assert_eq!(edit_area.buffer.caret(), Location::new(6, 0));
+        // caret 处不是括号时, 使用 caret 之前的括号.
+        edit_area.move_caret_to(Location::new(9, 0)).unwrap();
+        edit_area.move_caret(CaretMove::MatchingBracket);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 1));
+        assert_eq!(edit_area.screen_position(Location::new(8, 0)), Some(Location::new(8, 0)));
+        edit_area.move_caret(CaretMove::PrevTrace);
+        assert_eq!(edit_area.buffer.caret(), Location::new(9, 0));
+    }
+
+    #[test]
+    fn auto_close() {
+        let mut edit_area = edit_area_with("");
+        edit_area.set_settings(Settings { auto_close: true, ..Settings::default() });
+        for c in "f(\"a\")".chars() {
+            edit_area.insert_char(c);
+        }
+        assert_eq!(edit_area.buffer.to_string(), "f(\"a\")");
+        assert_eq!(edit_area.buffer.caret(), Location::new(6, 0));
+        edit_area.insert_char('\'');
+        edit_area.insert_char('x');
+        edit_area.insert_char('\'');
+        assert_eq!(edit_area.buffer.to_string(), "f(\"a\")'x'");
+        edit_area.move_caret_to(Location::new(2, 0)).unwrap();
+        edit_area.insert_char('[');
+        assert_eq!(edit_area.buffer.to_string(), "f([\"a\")'x'");
+        edit_area.insert_char(' ');
+        edit_area.move_caret(CaretMove::Left);
+        edit_area.insert_char('{');
+        assert_eq!(edit_area.buffer.to_string(), "f([{} \"a\")'x'");
+        edit_area.delete_backward().unwrap();
+        assert_eq!(edit_area.buffer.to_string(), "f([ \"a\")'x'");
+    }
+
+    #[test]
+    fn line_numbers_and_wrap() {
+        let mut edit_area = edit_area_with(&format!("{}\nb\n{}", "a".repeat(25), "c".repeat(30)));
+        let mut settings = Settings { line_numbers: true, ..Settings::default() };
+        edit_area.set_settings(settings);
+        // 3 行文本的行号栏宽度为 1 + 1.
+        edit_area.move_caret_to(Location::new(1, 1)).unwrap();
+        assert_eq!(edit_area.get_cursor(), Location::new(3, 1));
+
+        settings.wrap = true;
+        settings.vertical_padding = 0;
+        edit_area.configure_area(Area::new(0, 0, 12, 4));
+        edit_area.set_settings(settings);
+        edit_area.move_caret_to(Location::new(25, 0)).unwrap();
+        assert_eq!(edit_area.get_cursor(), Location::new(7, 2));
+        edit_area.move_caret_to(Location::new(20, 0)).unwrap();
+        assert_eq!(edit_area.get_cursor(), Location::new(2, 2));
+        // 第 3 行的第 3 个显示行超出了显示区域, 需要滚动, caret 在恰好占满显示行的行末.
+        edit_area.move_caret_to(Location::new(30, 2)).unwrap();
+        assert_eq!(edit_area.buffer_display_offset, Location::new(0, 1));
+        assert_eq!(edit_area.get_cursor(), Location::new(12, 3));
+    }
+
+    #[test]
+    fn multiple_cursors() {
+        let mut edit_area = edit_area_with("let a = 1;\nlet a = 2;\nlet b = a;");
+        edit_area.move_caret_to(Location::new(4, 0)).unwrap();
+        edit_area.add_next_occurrence();
+        assert_eq!(edit_area.buffer.region(), Some((Location::new(4, 0), Location::new(5, 0))));
+        edit_area.add_next_occurrence();
+        edit_area.add_next_occurrence();
+        // 已经选中了所有的 a, 继续查找会回到主光标的位置.
+        edit_area.add_next_occurrence();
+        assert_eq!(edit_area.buffer.cursors().len(), 2);
+        for c in "xy".chars() {
+            edit_area.insert_at_cursors(|edit_area| edit_area.insert_char(c));
+        }
+        assert_eq!(edit_area.buffer.to_string(), "let xy = 1;\nlet xy = 2;\nlet b = xy;");
+        assert_eq!(edit_area.buffer.caret(), Location::new(6, 0));
+        edit_area.delete_at_cursors(|edit_area| edit_area.delete_backward().unwrap());
+        edit_area.for_each_cursor(|edit_area| {
+            edit_area.move_caret(CaretMove::LineStart);
+        });
+        edit_area.insert_at_cursors(|edit_area| edit_area.insert_newline(&[]));
+        assert_eq!(edit_area.buffer.to_string(), "\nlet x = 1;\n\nlet x = 2;\n\nlet b = x;");
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 1));
+        edit_area.clear_cursors();
+
+        // 在上下方添加光标时保持显示列, 跨行的选区被拆分为每行一个光标.
+        let mut edit_area = edit_area_with("a\tb\nabcdefgh\nab");
+        edit_area.move_caret_to(Location::new(2, 0)).unwrap();
+        edit_area.add_cursor_below();
+        edit_area.add_cursor_below();
+        edit_area.add_cursor_below();
+        let carets: Vec<Location> = edit_area.buffer.cursors().iter().map(|c| c.caret).collect();
+        assert_eq!(carets, vec![Location::new(4, 1), Location::new(2, 2)]);
+        edit_area.clear_cursors();
+        edit_area.set_mark();
+        edit_area.move_caret_to(Location::new(0, 2)).unwrap();
+        edit_area.split_selection_into_lines();
+        assert_eq!(edit_area.buffer.region(), Some((Location::new(2, 0), Location::new(3, 0))));
+        assert_eq!(edit_area.buffer.cursors().len(), 1);
+        edit_area.delete_at_cursors(|edit_area| edit_area.delete_backward().unwrap());
+        assert_eq!(edit_area.buffer.to_string(), "a\t\n\nab");
+    }
+
+    #[test]
+    fn block_selection() {
+        let mut edit_area = edit_area_with("name\tage\nbob\t42\nx\nann\t7");
+        edit_area.move_caret_to(Location::new(1, 0)).unwrap();
+        for caret_move in [CaretMove::Down, CaretMove::Down, CaretMove::Right, CaretMove::Right] {
+            edit_area.block_select(caret_move);
+        }
+        assert_eq!(edit_area.block_ranges(), Some((0..3, 1..3)));
+        // 第 3 行只有 1 列, 选区超出了行末.
+        assert_eq!(edit_area.buffer.caret(), Location::new(1, 2));
+        assert_eq!(edit_area.get_cursor(), Location::new(3, 2));
+        assert_eq!(edit_area.copy_block().as_deref(), Some("am\nob\n"));
+        assert!(!edit_area.has_block());
+
+        // 在第 5 列插入, 宽度不够的行用空格补齐.
+        edit_area.move_caret_to(Location::new(4, 0)).unwrap();
+        for _ in 0..3 {
+            edit_area.block_select(CaretMove::Down);
+        }
+        edit_area.block_insert('|');
+        edit_area.block_insert(' ');
+        assert_eq!(edit_area.buffer.to_string(), "name| \tage\nbob\t| 42\nx   | \nann\t| 7");
+        edit_area.block_delete(true);
+        edit_area.block_delete(true);
+        assert_eq!(edit_area.buffer.to_string(), "name\tage\nbob\t42\nx   \nann\t7");
+        edit_area.cancel_block();
+
+        let mut edit_area = edit_area_with("ab\ncd");
+        edit_area.move_caret_to(Location::new(1, 0)).unwrap();
+        edit_area.block_select(CaretMove::Down);
+        edit_area.block_select(CaretMove::Right);
+        assert_eq!(edit_area.kill_block().as_deref(), Some("b\nd"));
+        assert_eq!(edit_area.buffer.to_string(), "a\nc");
+        edit_area.move_caret_to(Location::new(0, 1)).unwrap();
+        edit_area.yank_block("12\n34");
+        assert_eq!(edit_area.buffer.to_string(), "a\n12c\n34");
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 1));
+    }
+
+    #[test]
+    fn mouse() {
+        let mut edit_area = edit_area_with("fn main() {\n\tlet 名字 = 1;\n}");
+        edit_area.set_settings(Settings { line_numbers: true, ..Settings::default() });
+        // 行号栏宽度为 2, 制表符占 4 列, 宽字符中间的列对应宽字符的开始.
+        assert_eq!(edit_area.location_at(Location::new(2, 0)), Location::new(0, 0));
+        assert_eq!(edit_area.location_at(Location::new(0, 1)), Location::new(0, 1));
+        assert_eq!(edit_area.location_at(Location::new(11, 1)), Location::new(5, 1));
+        assert_eq!(edit_area.location_at(Location::new(30, 1)), Location::new(16, 1));
+        assert_eq!(edit_area.location_at(Location::new(5, 8)), Location::new(1, 2));
+
+        edit_area.click(Location::new(5, 0), 1);
+        assert_eq!(edit_area.buffer.caret(), Location::new(3, 0));
+        assert_eq!(edit_area.buffer.region(), None);
+        edit_area.drag_to(Location::new(3, 1));
+        assert_eq!(edit_area.buffer.region(), Some((Location::new(3, 0), Location::new(0, 1))));
+        edit_area.click(Location::new(5, 0), 2);
+        assert_eq!(edit_area.buffer.region(), Some((Location::new(3, 0), Location::new(7, 0))));
+        edit_area.click(Location::new(5, 0), 3);
+        assert_eq!(edit_area.buffer.region(), Some((Location::new(0, 0), Location::new(0, 1))));
+        // 显示区域之外的点击被忽略.
+        edit_area.click(Location::new(5, 20), 1);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 1));
+
+        let mut edit_area = edit_area_with(&"line\n".repeat(30));
+        edit_area.scroll(3);
+        assert_eq!(edit_area.buffer_display_offset.y, 3);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 0));
+        assert!(!edit_area.caret_visible());
+        edit_area.scroll(100);
+        assert_eq!(edit_area.buffer_display_offset.y, 21);
+        edit_area.scroll(-100);
+        assert_eq!(edit_area.buffer_display_offset.y, 0);
+        assert!(edit_area.caret_visible());
+    }
+
+    #[test]
+    fn scroll_view() {
+        let mut edit_area = edit_area_with(&"line\n".repeat(30));
+        edit_area.set_settings(Settings { vertical_padding: 2, ..Settings::default() });
+        edit_area.move_caret_to(Location::new(2, 5)).unwrap();
+        // caret 仍然在显示区域内时不移动.
+        edit_area.scroll_view(3);
+        assert_eq!(edit_area.buffer_display_offset.y, 3);
+        assert_eq!(edit_area.buffer.caret(), Location::new(2, 5));
+        // caret 进入了上方的滚动边距, 被移动到边距之下.
+        edit_area.scroll_view(4);
+        assert_eq!(edit_area.buffer_display_offset.y, 7);
+        assert_eq!(edit_area.buffer.caret(), Location::new(2, 9));
+        edit_area.scroll_view(-7);
+        assert_eq!(edit_area.buffer_display_offset.y, 0);
+        assert_eq!(edit_area.buffer.caret(), Location::new(2, 8));
+
+        edit_area.move_caret_to(Location::new(0, 15)).unwrap();
+        edit_area.center_caret();
+        assert_eq!(edit_area.buffer_display_offset.y, 10);
+        edit_area.move_caret_to(Location::new(0, 29)).unwrap();
+        edit_area.center_caret();
+        assert_eq!(edit_area.buffer_display_offset.y, 21);
+    }
+
+    #[test]
+    fn goal_column() {
+        let mut edit_area = edit_area_with("hello world\n\nhi\n\tworld\nhello world");
+        edit_area.move_caret_to(Location::new(8, 0)).unwrap();
+        edit_area.move_caret(CaretMove::Down);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 1));
+        edit_area.move_caret(CaretMove::Down);
+        assert_eq!(edit_area.buffer.caret(), Location::new(2, 2));
+        // 期望列是显示列, 制表符占 4 列.
+        edit_area.move_caret(CaretMove::Down);
+        assert_eq!(edit_area.buffer.caret(), Location::new(5, 3));
+        edit_area.move_caret(CaretMove::PageDown);
+        assert_eq!(edit_area.buffer.caret(), Location::new(8, 4));
+        edit_area.move_caret(CaretMove::PageUp);
+        assert_eq!(edit_area.buffer.caret(), Location::new(8, 0));
+
+        // 水平移动和编辑之后使用新的列.
+        edit_area.move_caret(CaretMove::Down);
+        edit_area.move_caret(CaretMove::Down);
+        edit_area.move_caret(CaretMove::Left);
+        // 第 1 列在制表符中间.
+        edit_area.move_caret(CaretMove::Down);
+        assert_eq!(edit_area.buffer.caret(), Location::new(0, 3));
+        edit_area.move_caret(CaretMove::Up);
+        write!(edit_area, "!").unwrap();
+        edit_area.move_caret(CaretMove::Down);
+        edit_area.move_caret(CaretMove::Up);
+        assert_eq!(edit_area.buffer.caret(), Location::new(2, 2));
+    }
+
+    #[test]
+    fn word_motion() {
+        let mut edit_area = edit_area_with("foo.bar(baz)\n\n  名字 = parseHTTP;");
+        let end = Location::new(21, 2);
+        let mut starts = Vec::new();
+        while edit_area.buffer.caret() != end {
+            edit_area.move_caret(CaretMove::NextWord);
+            starts.push(edit_area.buffer.caret());
+        }
+        let expected = [(3, 0), (4, 0), (7, 0), (8, 0), (11, 0), (2, 2), (9, 2), (11, 2), (20, 2), (21, 2)];
+        assert_eq!(starts, expected.map(|(x, y)| Location::new(x, y)));
+        edit_area.move_caret(CaretMove::PrevWord);
+        assert_eq!(edit_area.buffer.caret(), Location::new(20, 2));
+
+        edit_area.set_settings(Settings { subword: true, ..Settings::default() });
+        edit_area.move_caret(CaretMove::PrevWord);
+        assert_eq!(edit_area.buffer.caret(), Location::new(16, 2));
+        edit_area.move_caret_to(Location::new(5, 2)).unwrap();
+        edit_area.move_caret(CaretMove::PrevWord);
+        assert_eq!(edit_area.buffer.caret(), Location::new(2, 2));
+        edit_area.move_caret(CaretMove::PrevWord);
+        assert_eq!(edit_area.buffer.caret(), Location::new(11, 0));
+    }
+}
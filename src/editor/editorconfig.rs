@@ -0,0 +1,422 @@
+use std::fs;
+use std::path::{Component, Path};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use crate::editor::buffer::{Buffer, LineEnding, SaveOptions};
+use crate::editor::settings::{IndentStyle, SettingsLayer};
+
+/// EditorConfig 文件的文件名.
+const FILE_NAME: &str = ".editorconfig";
+
+/// `indent_size` 属性的值.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum IndentSize {
+    /// 缩进的列数.
+    Columns(usize),
+    /// 缩进的宽度等于 `tab_width`.
+    Tab,
+}
+
+/// 从 [EditorConfig](https://editorconfig.org) 文件中得到的针对某个文件的属性, 为 None 的属性没有被设置.
+///
+/// 支持的属性:
+/// - `indent_style`, `indent_size`, `tab_width`: 对应设置项 `indentstyle`, `indentsize` 和 `tabwidth`, 见 [`EditorConfig::settings_layer`].
+/// - `end_of_line`, `charset`, `insert_final_newline`, `trim_trailing_whitespace`: 作用于 buffer 的加载和保存.
+///
+/// 不支持的属性和值会被忽略.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct EditorConfig {
+    indent_style: Option<IndentStyle>,
+    indent_size: Option<IndentSize>,
+    tab_width: Option<usize>,
+    end_of_line: Option<LineEnding>,
+    /// 编码和保存时是否写入 BOM, 为 None 时 BOM 保持加载时的状态.
+    charset: Option<(&'static Encoding, Option<bool>)>,
+    insert_final_newline: Option<bool>,
+    trim_trailing_whitespace: Option<bool>,
+}
+
+/// 解析后的一个 EditorConfig 文件.
+struct ConfigFile {
+    /// 是否在此文件之后停止向上层目录查找.
+    root: bool,
+    /// 各个段落的 glob 和其中的属性, 按照在文件中出现的顺序.
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl EditorConfig {
+    /// 查找并解析作用于 path 的 `.editorconfig` 文件.
+    ///
+    /// 从 path 所在的目录开始向上查找, 直到遇到包含 `root = true` 的文件或者到达根目录,
+    /// 越靠近 path 的文件优先级越高, 同一个文件中越靠后的段落优先级越高.
+    ///
+    /// 无法读取的 `.editorconfig` 文件会被忽略.
+    pub fn find(path: impl AsRef<Path>) -> EditorConfig {
+        let mut config = EditorConfig::default();
+        let Ok(path) = std::path::absolute(path) else {
+            return config;
+        };
+        let mut files = Vec::new();
+        for dir in path.ancestors().skip(1) {
+            let Ok(content) = fs::read_to_string(dir.join(FILE_NAME)) else {
+                continue;
+            };
+            let file = ConfigFile::parse(&content);
+            let root = file.root;
+            files.push((dir, file));
+            if root {
+                break;
+            }
+        }
+        for (dir, file) in files.iter().rev() {
+            let Some(relative) = relative_path(&path, dir) else {
+                continue;
+            };
+            for (glob, properties) in &file.sections {
+                if section_matches(glob, &relative) {
+                    properties.iter().for_each(|(key, value)| config.set(key, value));
+                }
+            }
+        }
+        config
+    }
+
+    /// 设置一个属性, 值为 `unset` 时取消属性的设置.
+    fn set(&mut self, key: &str, value: &str) {
+        let unset = value == "unset";
+        let flag = || match value {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        };
+        match key {
+            "indent_style" if unset => self.indent_style = None,
+            "indent_style" => self.indent_style = value.parse().ok().or(self.indent_style),
+            "indent_size" if unset => self.indent_size = None,
+            "indent_size" if value == "tab" => self.indent_size = Some(IndentSize::Tab),
+            "indent_size" => {
+                if let Some(size) = value.parse().ok().filter(|size| *size > 0) {
+                    self.indent_size = Some(IndentSize::Columns(size));
+                }
+            }
+            "tab_width" if unset => self.tab_width = None,
+            "tab_width" => self.tab_width = value.parse().ok().filter(|width| *width > 0).or(self.tab_width),
+            "end_of_line" if unset => self.end_of_line = None,
+            // 不支持 `cr`.
+            "end_of_line" => self.end_of_line = value.parse().ok().or(self.end_of_line),
+            "charset" if unset => self.charset = None,
+            "charset" => {
+                self.charset = match value {
+                    "latin1" => Some((WINDOWS_1252, None)),
+                    "utf-8" => Some((UTF_8, Some(false))),
+                    "utf-8-bom" => Some((UTF_8, Some(true))),
+                    "utf-16be" => Some((UTF_16BE, None)),
+                    "utf-16le" => Some((UTF_16LE, None)),
+                    _ => self.charset,
+                }
+            }
+            "insert_final_newline" if unset => self.insert_final_newline = None,
+            "insert_final_newline" => self.insert_final_newline = flag().or(self.insert_final_newline),
+            "trim_trailing_whitespace" if unset => self.trim_trailing_whitespace = None,
+            "trim_trailing_whitespace" => self.trim_trailing_whitespace = flag().or(self.trim_trailing_whitespace),
+            _ => {}
+        }
+    }
+
+    /// 缩进相关的属性对应的设置.
+    ///
+    /// 没有设置 `tab_width` 时使用数值形式的 `indent_size` 作为 `tabwidth`,
+    /// `indent_size = tab` 时使用 `tab_width` 作为 `indentsize`.
+    pub fn settings_layer(&self) -> SettingsLayer {
+        let indent_size = match self.indent_size {
+            Some(IndentSize::Columns(size)) => Some(size),
+            Some(IndentSize::Tab) => self.tab_width,
+            None => None,
+        };
+        let tab_width = self.tab_width.or(match self.indent_size {
+            Some(IndentSize::Columns(size)) => Some(size),
+            _ => None,
+        });
+        SettingsLayer { indent_style: self.indent_style, indent_size, tab_width, ..SettingsLayer::default() }
+    }
+
+    /// 在加载文件之前设置 buffer 解码时使用的编码.
+    pub fn apply_before_load(&self, buffer: &mut Buffer) {
+        if let Some((encoding, _)) = self.charset {
+            buffer.set_encoding(encoding);
+        }
+    }
+
+    /// 在加载文件之后设置 buffer 保存时使用的换行符, BOM 和对文本的处理.
+    pub fn apply_after_load(&self, buffer: &mut Buffer) {
+        if let Some(line_ending) = self.end_of_line {
+            buffer.set_line_ending(line_ending);
+        }
+        if let Some((_, Some(bom))) = self.charset {
+            buffer.set_bom(bom);
+        }
+        buffer.set_save_options(SaveOptions {
+            trim_trailing_whitespace: self.trim_trailing_whitespace.unwrap_or(false),
+            insert_final_newline: self.insert_final_newline,
+        });
+    }
+}
+
+impl ConfigFile {
+    /// 解析 INI 格式的文件内容, 无法识别的行会被忽略.
+    fn parse(content: &str) -> ConfigFile {
+        let mut file = ConfigFile { root: false, sections: Vec::new() };
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(glob) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                file.sections.push((glob.to_owned(), Vec::new()));
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim().to_lowercase(), value.trim().to_lowercase());
+            match file.sections.last_mut() {
+                Some((_, properties)) => properties.push((key, value)),
+                // 第一个段落之前只有 `root` 属性有效.
+                None if key == "root" => file.root = value == "true",
+                None => {}
+            }
+        }
+        file
+    }
+}
+
+/// path 相对于 dir 的路径, 使用 `/` 分隔.
+fn relative_path(path: &Path, dir: &Path) -> Option<String> {
+    let components: Option<Vec<&str>> = path
+        .strip_prefix(dir)
+        .ok()?
+        .components()
+        .map(|c| match c {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+    Some(components?.join("/"))
+}
+
+/// 判断段落的 glob 是否匹配文件.
+///
+/// 不包含 `/` 的 glob 匹配文件名, 包含 `/` 的 glob 匹配文件相对于 `.editorconfig` 所在目录的路径.
+///
+/// * `relative`: 文件相对于 `.editorconfig` 所在目录的路径, 使用 `/` 分隔.
+fn section_matches(glob: &str, relative: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    if glob.contains(&'/') {
+        let glob = glob.strip_prefix(&['/']).unwrap_or(&glob);
+        glob_matches(glob, &relative.chars().collect::<Vec<_>>())
+    } else {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        glob_matches(&glob, &name.chars().collect::<Vec<_>>())
+    }
+}
+
+/// 使用 EditorConfig 的通配符规则匹配文本:
+/// - `*`: 匹配除了 `/` 之外的任意字符串.
+/// - `**`: 匹配任意字符串.
+/// - `?`: 匹配除了 `/` 之外的任意一个字符.
+/// - `[name]`, `[!name]`: 匹配 (不) 在 name 中的一个字符, name 中可以使用 `a-z` 表示范围.
+/// - `{s1,s2,s3}`: 匹配其中任意一个字符串, 可以嵌套.
+/// - `{num1..num2}`: 匹配 num1 和 num2 之间的整数.
+/// - `\`: 转义下一个字符.
+fn glob_matches(glob: &[char], text: &[char]) -> bool {
+    let Some(&first) = glob.first() else {
+        return text.is_empty();
+    };
+    match first {
+        '\\' if glob.len() > 1 => text.first() == Some(&glob[1]) && glob_matches(&glob[2..], &text[1..]),
+        '*' if glob.get(1) == Some(&'*') => (0..=text.len()).any(|i| glob_matches(&glob[2..], &text[i..])),
+        '*' => {
+            let max = text.iter().position(|c| *c == '/').unwrap_or(text.len());
+            (0..=max).any(|i| glob_matches(&glob[1..], &text[i..]))
+        }
+        '?' => text.first().is_some_and(|c| *c != '/') && glob_matches(&glob[1..], &text[1..]),
+        '[' => match class_end(glob) {
+            Some(end) => text.first().is_some_and(|c| class_matches(&glob[1..end], *c)) && glob_matches(&glob[end + 1..], &text[1..]),
+            None => literal_matches(glob, text),
+        },
+        '{' => match brace_end(glob) {
+            Some(end) => braces_match(&glob[1..end], &glob[end + 1..], text),
+            None => literal_matches(glob, text),
+        },
+        _ => literal_matches(glob, text),
+    }
+}
+
+/// glob 的第一个字符按照普通字符匹配.
+fn literal_matches(glob: &[char], text: &[char]) -> bool {
+    text.first() == Some(&glob[0]) && glob_matches(&glob[1..], &text[1..])
+}
+
+/// 以 `[` 开头的 glob 中字符类的 `]` 的位置.
+fn class_end(glob: &[char]) -> Option<usize> {
+    let start = if glob.get(1) == Some(&'!') { 2 } else { 1 };
+    // 紧跟在 `[` 或 `[!` 之后的 `]` 作为普通字符.
+    (start + 1..glob.len()).find(|i| glob[*i] == ']' && glob[*i - 1] != '\\')
+}
+
+/// 字符是否在字符类中, class 为 `[` 和 `]` 之间的内容.
+fn class_matches(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.strip_prefix(&['!']) {
+        Some(class) => (true, class),
+        None => (false, class),
+    };
+    let mut matched = false;
+    let mut i = 0;
+    while i < class.len() {
+        let low = class[i];
+        if class.get(i + 1) == Some(&'-') && i + 2 < class.len() {
+            matched |= (low..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= low == c;
+            i += 1;
+        }
+    }
+    matched != negated
+}
+
+/// 以 `{` 开头的 glob 中与之对应的 `}` 的位置.
+fn brace_end(glob: &[char]) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in glob.iter().enumerate() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 匹配 `{...}` 以及其后的 glob.
+///
+/// * `inner`: `{` 和 `}` 之间的内容.
+/// * `rest`: `}` 之后的 glob.
+fn braces_match(inner: &[char], rest: &[char], text: &[char]) -> bool {
+    let inner_str: String = inner.iter().collect();
+    if let Some((low, high)) = inner_str.split_once("..") {
+        if let (Ok(low), Ok(high)) = (low.parse::<i64>(), high.parse::<i64>()) {
+            return (1..=text.len()).any(|i| {
+                let number: String = text[..i].iter().collect();
+                number.parse::<i64>().is_ok_and(|n| (low.min(high)..=low.max(high)).contains(&n))
+                    && glob_matches(rest, &text[i..])
+            });
+        }
+    }
+    let alternatives = split_alternatives(inner);
+    if alternatives.len() < 2 {
+        // 只有一个选项的 `{single}` 按照普通字符匹配.
+        let mut glob = vec!['\\', '{'];
+        glob.extend_from_slice(inner);
+        glob.extend_from_slice(&['\\', '}']);
+        glob.extend_from_slice(rest);
+        return glob_matches(&glob, text);
+    }
+    alternatives.into_iter().any(|alternative| {
+        let mut glob = alternative.to_vec();
+        glob.extend_from_slice(rest);
+        glob_matches(&glob, text)
+    })
+}
+
+/// 使用不在嵌套的 `{}` 中的 `,` 分隔选项.
+fn split_alternatives(inner: &[char]) -> Vec<&[char]> {
+    let mut alternatives = Vec::new();
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in inner.iter().enumerate() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                alternatives.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    alternatives.push(&inner[start..]);
+    alternatives
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crate::editor::buffer::LineEnding;
+    use crate::editor::editorconfig::{section_matches, EditorConfig};
+    use crate::editor::settings::IndentStyle;
+
+    #[test]
+    fn glob() {
+        assert!(section_matches("*", "src/main.rs"));
+        assert!(section_matches("*.rs", "src/main.rs"));
+        assert!(!section_matches("*.rs", "src/main.rs.bak"));
+        assert!(section_matches("*.{js,ts}", "web/app.ts"));
+        assert!(section_matches("{Makefile,*.mk}", "Makefile"));
+        assert!(section_matches("src/*.rs", "src/main.rs"));
+        assert!(!section_matches("src/*.rs", "src/editor/buffer.rs"));
+        assert!(!section_matches("src/*.rs", "lib/src/main.rs"));
+        assert!(section_matches("/src/**.rs", "src/editor/buffer.rs"));
+        assert!(section_matches("file?.txt", "file1.txt"));
+        assert!(!section_matches("file?.txt", "file10.txt"));
+        assert!(section_matches("file[0-9].txt", "file7.txt"));
+        assert!(!section_matches("file[!0-9].txt", "file7.txt"));
+        assert!(section_matches("file{1..12}.txt", "file10.txt"));
+        assert!(!section_matches("file{1..12}.txt", "file13.txt"));
+        assert!(section_matches("{single}.txt", "{single}.txt"));
+        assert!(section_matches("\\*.txt", "*.txt"));
+        assert!(!section_matches("\\*.txt", "a.txt"));
+    }
+
+    #[test]
+    fn find() {
+        let root = std::env::temp_dir().join(format!("vegetor-editorconfig-{}", std::process::id()));
+        let sub = root.join("sub");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(
+            root.join(".editorconfig"),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 2\nend_of_line = crlf\ncharset = latin1\n\n\
+             [*.mk]\nindent_style = tab\n\n[sub/*.txt]\ninsert_final_newline = true\n",
+        )
+        .unwrap();
+        fs::write(sub.join(".editorconfig"), "# comment\n[*.txt]\nindent_size = tab\ntab_width = 8\ncharset = unset\n").unwrap();
+
+        let config = EditorConfig::find(sub.join("a.txt"));
+        let layer = config.settings_layer();
+        assert_eq!(layer.indent_style, Some(IndentStyle::Space));
+        assert_eq!(layer.indent_size, Some(8));
+        assert_eq!(layer.tab_width, Some(8));
+        assert_eq!(config.end_of_line, Some(LineEnding::CrLf));
+        assert_eq!(config.charset, None);
+        assert_eq!(config.insert_final_newline, Some(true));
+
+        let config = EditorConfig::find(root.join("rules.mk"));
+        let layer = config.settings_layer();
+        assert_eq!(layer.indent_style, Some(IndentStyle::Tab));
+        assert_eq!(layer.tab_width, Some(2));
+        assert_eq!(config.charset, Some((encoding_rs::WINDOWS_1252, None)));
+        assert_eq!(config.insert_final_newline, None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
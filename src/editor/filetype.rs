@@ -0,0 +1,85 @@
+use std::path::Path;
+
+/// 文件类型, 用于按照文件类型覆盖设置.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Filetype {
+    /// 文件类型的名称, 用于配置文件中的 `[filetype.<name>]`.
+    pub name: &'static str,
+    /// 此类型的文件的扩展名, 不包括 `.`.
+    pub extensions: &'static [&'static str],
+    /// 没有扩展名或者扩展名无法区分类型时, 通过完整的文件名判断, 比如 `Makefile`.
+    pub file_names: &'static [&'static str],
+    /// 行末 (忽略末尾空白) 为这些字符时, 自动缩进会让下一行增加一级缩进, 见 [`INDENT_AFTER_BRACKETS`].
+    pub indent_after: &'static [char],
+    /// 切换注释时使用的注释语法, 没有注释语法时为 None.
+    pub comment: Option<Comment>,
+}
+
+/// 语言的注释语法, 同时支持两种注释的语言优先使用行注释.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Comment {
+    /// 行注释的开始标记, 比如 `//`.
+    Line(&'static str),
+    /// 块注释的开始和结束标记, 比如 `/*` 和 `*/`.
+    Block(&'static str, &'static str),
+}
+
+/// 使用括号划分代码块的语言的 [`Filetype::indent_after`].
+const INDENT_AFTER_BRACKETS: &[char] = &['{', '[', '('];
+
+/// 所有已知的文件类型.
+pub const FILETYPES: &[Filetype] = &[
+    Filetype { name: "rust", extensions: &["rs"], file_names: &[], indent_after: INDENT_AFTER_BRACKETS, comment: Some(Comment::Line("//")) },
+    Filetype { name: "c", extensions: &["c", "h"], file_names: &[], indent_after: INDENT_AFTER_BRACKETS, comment: Some(Comment::Line("//")) },
+    Filetype { name: "cpp", extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"], file_names: &[], indent_after: INDENT_AFTER_BRACKETS, comment: Some(Comment::Line("//")) },
+    Filetype { name: "python", extensions: &["py", "pyi"], file_names: &[], indent_after: &[':', '{', '[', '('], comment: Some(Comment::Line("#")) },
+    Filetype { name: "javascript", extensions: &["js", "mjs", "cjs"], file_names: &[], indent_after: INDENT_AFTER_BRACKETS, comment: Some(Comment::Line("//")) },
+    Filetype { name: "typescript", extensions: &["ts", "tsx"], file_names: &[], indent_after: INDENT_AFTER_BRACKETS, comment: Some(Comment::Line("//")) },
+    Filetype { name: "go", extensions: &["go"], file_names: &[], indent_after: INDENT_AFTER_BRACKETS, comment: Some(Comment::Line("//")) },
+    Filetype { name: "java", extensions: &["java"], file_names: &[], indent_after: INDENT_AFTER_BRACKETS, comment: Some(Comment::Line("//")) },
+    Filetype { name: "lua", extensions: &["lua"], file_names: &[], indent_after: &['{', '('], comment: Some(Comment::Line("--")) },
+    Filetype { name: "shell", extensions: &["sh", "bash", "zsh"], file_names: &[".bashrc", ".zshrc", ".profile"], indent_after: &['{', '('], comment: Some(Comment::Line("#")) },
+    Filetype { name: "makefile", extensions: &["mk"], file_names: &["Makefile", "makefile", "GNUmakefile"], indent_after: &[':'], comment: Some(Comment::Line("#")) },
+    Filetype { name: "toml", extensions: &["toml"], file_names: &[], indent_after: &['[', '{'], comment: Some(Comment::Line("#")) },
+    Filetype { name: "json", extensions: &["json"], file_names: &[], indent_after: &['{', '['], comment: None },
+    Filetype { name: "yaml", extensions: &["yml", "yaml"], file_names: &[], indent_after: &[':'], comment: Some(Comment::Line("#")) },
+    Filetype { name: "markdown", extensions: &["md", "markdown"], file_names: &[], indent_after: &[], comment: Some(Comment::Block("<!--", "-->")) },
+    Filetype { name: "html", extensions: &["html", "htm"], file_names: &[], indent_after: &[], comment: Some(Comment::Block("<!--", "-->")) },
+    Filetype { name: "css", extensions: &["css"], file_names: &[], indent_after: &['{'], comment: Some(Comment::Block("/*", "*/")) },
+    Filetype { name: "sql", extensions: &["sql"], file_names: &[], indent_after: &['('], comment: Some(Comment::Line("--")) },
+    Filetype { name: "text", extensions: &["txt"], file_names: &[], indent_after: &[], comment: None },
+];
+
+impl Filetype {
+    /// 根据文件名判断文件类型, 先匹配完整的文件名, 再匹配扩展名.
+    pub fn detect(path: impl AsRef<Path>) -> Option<&'static Filetype> {
+        let path = path.as_ref();
+        let file_name = path.file_name()?.to_str()?;
+        if let Some(filetype) = FILETYPES.iter().find(|t| t.file_names.contains(&file_name)) {
+            return Some(filetype);
+        }
+        let extension = path.extension()?.to_str()?;
+        FILETYPES.iter().find(|t| t.extensions.contains(&extension))
+    }
+
+    /// 通过名称查找文件类型.
+    pub fn by_name(name: &str) -> Option<&'static Filetype> {
+        FILETYPES.iter().find(|t| t.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::filetype::{Comment, Filetype};
+
+    #[test]
+    fn detect() {
+        assert_eq!(Filetype::detect("src/lib.rs").unwrap().name, "rust");
+        assert_eq!(Filetype::detect("/tmp/Makefile").unwrap().name, "makefile");
+        assert_eq!(Filetype::detect("include/a.hpp").unwrap().name, "cpp");
+        assert!(Filetype::detect("LICENSE").is_none());
+        assert!(Filetype::detect("a.unknown").is_none());
+        assert_eq!(Filetype::by_name("python").unwrap().extensions, ["py", "pyi"]);
+        assert_eq!(Filetype::by_name("sql").unwrap().comment, Some(Comment::Line("--")));
+    }
+}
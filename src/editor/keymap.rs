@@ -0,0 +1,537 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use crate::editor::command::Command;
+use crate::editor::editarea::CaretMove;
+use crate::error;
+
+/// 有名称的按键, 名称不区分大小写, 同一按键的多个名称中第一个用于显示.
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("SPC", KeyCode::Char(' ')),
+    ("Space", KeyCode::Char(' ')),
+    ("RET", KeyCode::Enter),
+    ("Enter", KeyCode::Enter),
+    ("TAB", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("ESC", KeyCode::Esc),
+    ("DEL", KeyCode::Backspace),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+/// 单个按键组合, 比如 `Ctrl-x`.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// 创建按键组合, 会对字符按键的 SHIFT 修饰进行规范化:
+    /// 字符本身已经体现了大小写 (比如 `A`, `<`), 因此去掉 SHIFT 修饰.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        KeyChord { code, modifiers }
+    }
+
+    /// 没有修饰键的按键.
+    pub fn key(code: KeyCode) -> KeyChord {
+        KeyChord::new(code, KeyModifiers::NONE)
+    }
+
+    /// `Ctrl` + 按键.
+    pub fn ctrl(code: KeyCode) -> KeyChord {
+        KeyChord::new(code, KeyModifiers::CONTROL)
+    }
+
+    /// `Alt` (Meta) + 按键.
+    pub fn alt(code: KeyCode) -> KeyChord {
+        KeyChord::new(code, KeyModifiers::ALT)
+    }
+
+    /// 此按键是否为直接输入字符的按键, 如果是则返回该字符.
+    pub fn self_insert_char(&self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(ch) if self.modifiers == KeyModifiers::NONE => Some(ch),
+            _ => None,
+        }
+    }
+}
+
+/// 按键组合的文本表示, 使用 Emacs 的写法, 比如 `C-x`, `M-f`, `C-M-Left`, `S-TAB`.
+///
+/// - 修饰键: `C-` (Ctrl), `M-` 或 `A-` (Alt), `S-` (Shift, 只对非字符按键有效).
+/// - 按键: 单个字符, 功能键 `F1` ~ `F24`, 以及 [`NAMED_KEYS`] 中的按键名称.
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "C-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "M-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "S-")?;
+        }
+        match NAMED_KEYS.iter().find(|(_, code)| *code == self.code) {
+            Some((name, _)) => write!(f, "{name}"),
+            None => match self.code {
+                KeyCode::Char(ch) => write!(f, "{ch}"),
+                KeyCode::F(n) => write!(f, "F{n}"),
+                code => write!(f, "{code:?}"),
+            },
+        }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = error::Error;
+
+    /// 解析按键组合, 格式见 [`KeyChord`] 的 [`Display`](fmt::Display) 实现.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::InvalidKeyChord`]
+    fn from_str(s: &str) -> error::Result<KeyChord> {
+        let invalid = || error::Error::InvalidKeyChord(s.to_owned());
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // 最后一个字符本身可能是 '-', 比如 `C--`.
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            modifiers |= match &rest[..1] {
+                "C" => KeyModifiers::CONTROL,
+                "M" | "A" => KeyModifiers::ALT,
+                "S" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+            rest = &rest[2..];
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            (Some(_), Some(_)) => NAMED_KEYS.iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(rest))
+                .map(|(_, code)| *code)
+                .or_else(|| match rest.strip_prefix(['F', 'f'])?.parse() {
+                    Ok(n @ 1..=24) => Some(KeyCode::F(n)),
+                    _ => None,
+                })
+                .ok_or_else(invalid)?,
+            (None, _) => return Err(invalid()),
+        };
+        Ok(KeyChord::new(code, modifiers))
+    }
+}
+
+/// 解析由空白分隔的按键序列, 比如 `C-x C-s`.
+///
+/// # Errors
+///
+/// - [`error::Error::InvalidKeyChord`]: 序列中有无效的按键组合, 或者序列为空.
+pub fn parse_key_sequence(s: &str) -> error::Result<Vec<KeyChord>> {
+    let keys = s.split_whitespace()
+        .map(KeyChord::from_str)
+        .collect::<error::Result<Vec<KeyChord>>>()?;
+    if keys.is_empty() {
+        Err(error::Error::InvalidKeyChord(s.to_owned()))
+    } else {
+        Ok(keys)
+    }
+}
+
+/// 按键序列的文本表示, 按键组合之间使用空格分隔.
+pub fn format_key_sequence(keys: &[KeyChord]) -> String {
+    keys.iter().map(KeyChord::to_string).collect::<Vec<String>>().join(" ")
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(value: &KeyEvent) -> KeyChord {
+        KeyChord::new(value.code, value.modifiers)
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(value: KeyEvent) -> KeyChord {
+        (&value).into()
+    }
+}
+
+/// [`Keymap::lookup`] 的查找结果.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum KeyLookup {
+    /// 按键序列完整匹配了一个命令.
+    Command(Command),
+    /// 按键序列是某些绑定的前缀, 需要继续等待按键.
+    Prefix,
+    /// 按键序列没有对应的绑定.
+    Unbound,
+}
+
+/// 内置的按键绑定预设.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeymapPreset {
+    /// 默认的按键绑定, 方向键移动, Ctrl-Q 退出, Ctrl-S 保存, Ctrl-P 打开命令面板, Ctrl-G 跳转.
+    #[default]
+    Standard,
+    /// Emacs 风格的按键绑定.
+    Emacs,
+}
+
+/// 按键绑定配置文件 (`keys.toml`) 的内容, 例如:
+///
+/// ```toml
+/// # 在此预设的基础上修改, 可选 "standard" 和 "emacs", 不填写时使用启动时指定的预设.
+/// preset = "emacs"
+/// # 需要移除的预设中的按键绑定.
+/// unbind = ["C-s"]
+///
+/// [bindings]
+/// "C-x C-s" = "save"
+/// "M-k" = "kill-line"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapConfig {
+    preset: Option<KeymapPreset>,
+    #[serde(default)]
+    unbind: Vec<String>,
+    #[serde(default)]
+    bindings: BTreeMap<String, String>,
+}
+
+/// 按键序列到 [`Command`] 的映射.
+///
+/// 一个绑定可以由多个按键组成 (比如 Emacs 的 `C-x C-s`),
+/// 此时前面的按键构成前缀, 前缀本身不能再绑定命令.
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    bindings: HashMap<Vec<KeyChord>, Command>,
+}
+
+impl Keymap {
+    /// 创建没有任何绑定的 keymap.
+    pub fn new() -> Keymap {
+        Keymap::default()
+    }
+
+    pub fn from_preset(preset: KeymapPreset) -> Keymap {
+        match preset {
+            KeymapPreset::Standard => Keymap::standard(),
+            KeymapPreset::Emacs => Keymap::emacs(),
+        }
+    }
+
+    /// 从按键绑定配置文件中加载 keymap, 配置文件格式见 [`KeymapConfig`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: 配置文件路径.
+    /// * `preset`: 配置文件中没有指定预设时使用的预设.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::ConfigFile`]: 包含文件路径和具体的错误, 具体错误见 [`Keymap::from_config_str`].
+    pub fn load(path: impl AsRef<Path>, preset: KeymapPreset) -> error::Result<Keymap> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(error::Error::from)
+            .and_then(|content| Keymap::from_config_str(&content, preset))
+            .map_err(|e| error::Error::ConfigFile { path: path.to_path_buf(), source: Box::new(e) })
+    }
+
+    /// 从配置文件内容中构建 keymap.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::ConfigParse`]: 配置内容不是有效的 TOML, 或者包含未知的字段.
+    /// - [`error::Error::InvalidKeyChord`]: 按键序列格式错误, 见 [`parse_key_sequence`].
+    /// - [`error::Error::UnknownCommand`]: 绑定了不存在的命令, 命令名称见 [`COMMANDS`](crate::editor::command::COMMANDS).
+    /// - [`error::Error::DuplicateBinding`]: 同一按键序列在配置中被绑定了多次 (比如 `M-f` 和 `A-f`).
+    /// - [`error::Error::ConflictingBinding`]: 见 [`Keymap::check_conflicts`].
+    pub fn from_config_str(content: &str, preset: KeymapPreset) -> error::Result<Keymap> {
+        let config: KeymapConfig = toml::from_str(content)?;
+        let mut keymap = Keymap::from_preset(config.preset.unwrap_or(preset));
+        for keys in &config.unbind {
+            keymap.bindings.remove(&parse_key_sequence(keys)?);
+        }
+        let mut user_bindings = HashMap::new();
+        for (keys, command) in &config.bindings {
+            let keys = parse_key_sequence(keys)?;
+            let command: Command = command.parse()?;
+            if user_bindings.insert(keys.clone(), command).is_some() {
+                return Err(error::Error::DuplicateBinding(format_key_sequence(&keys)));
+            }
+        }
+        keymap.bindings.extend(user_bindings);
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+
+    /// 检查是否有按键序列既绑定了命令, 又是其他绑定的前缀, 这样的绑定永远无法被触发.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::ConflictingBinding`]
+    pub fn check_conflicts(&self) -> error::Result<()> {
+        let mut sequences: Vec<&Vec<KeyChord>> = self.bindings.keys().collect();
+        // 排序以保证报错内容稳定.
+        sequences.sort_by_key(|keys| format_key_sequence(keys));
+        for keys in &sequences {
+            if let Some(longer) = sequences.iter()
+                .find(|longer| longer.len() > keys.len() && longer.starts_with(keys)) {
+                return Err(error::Error::ConflictingBinding {
+                    keys: format_key_sequence(keys),
+                    longer: format_key_sequence(longer),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// 绑定按键序列到命令, 已有的相同按键序列的绑定会被覆盖.
+    pub fn bind(&mut self, keys: impl Into<Vec<KeyChord>>, command: Command) {
+        self.bindings.insert(keys.into(), command);
+    }
+
+    /// 查找按键序列对应的命令.
+    pub fn lookup(&self, keys: &[KeyChord]) -> KeyLookup {
+        if let Some(command) = self.bindings.get(keys) {
+            return KeyLookup::Command(*command);
+        }
+        let is_prefix = self.bindings.keys()
+            .any(|seq| seq.len() > keys.len() && seq.starts_with(keys));
+        if is_prefix {
+            KeyLookup::Prefix
+        } else {
+            KeyLookup::Unbound
+        }
+    }
+
+    /// 默认的按键绑定.
+    pub fn standard() -> Keymap {
+        use KeyChord as K;
+        let mut keymap = Keymap::new();
+        let moves = [
+            (K::key(KeyCode::Left), CaretMove::Left),
+            (K::key(KeyCode::Right), CaretMove::Right),
+            (K::key(KeyCode::Up), CaretMove::Up),
+            (K::key(KeyCode::Down), CaretMove::Down),
+            (K::ctrl(KeyCode::Left), CaretMove::PrevWord),
+            (K::ctrl(KeyCode::Right), CaretMove::NextWord),
+            (K::new(KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::ALT), CaretMove::PrevTrace),
+            (K::new(KeyCode::Right, KeyModifiers::CONTROL | KeyModifiers::ALT), CaretMove::NextTrace),
+            (K::key(KeyCode::Home), CaretMove::LineStart),
+            (K::key(KeyCode::End), CaretMove::LineEnd),
+            (K::ctrl(KeyCode::Home), CaretMove::GlobalStart),
+            (K::ctrl(KeyCode::End), CaretMove::GlobalEnd),
+            (K::key(KeyCode::PageUp), CaretMove::PageUp),
+            (K::key(KeyCode::PageDown), CaretMove::PageDown),
+        ];
+        for (key, caret_move) in moves {
+            keymap.bind([key], Command::MoveCaret(caret_move));
+        }
+        let block_moves = [
+            (KeyCode::Up, CaretMove::Up),
+            (KeyCode::Down, CaretMove::Down),
+            (KeyCode::Left, CaretMove::Left),
+            (KeyCode::Right, CaretMove::Right),
+        ];
+        for (code, caret_move) in block_moves {
+            keymap.bind([K::new(code, KeyModifiers::ALT | KeyModifiers::SHIFT)], Command::BlockSelect(caret_move));
+        }
+        keymap.bind([K::ctrl(KeyCode::Char(']'))], Command::MoveCaret(CaretMove::MatchingBracket));
+        // 大部分终端中 C-] 会被读取为 C-5.
+        keymap.bind([K::ctrl(KeyCode::Char('5'))], Command::MoveCaret(CaretMove::MatchingBracket));
+        keymap.bind([K::ctrl(KeyCode::Char('q'))], Command::Quit);
+        keymap.bind([K::ctrl(KeyCode::Char('s'))], Command::Save);
+        keymap.bind([K::ctrl(KeyCode::Char('o'))], Command::Open);
+        keymap.bind([K::ctrl(KeyCode::Char('p'))], Command::Palette);
+        keymap.bind([K::ctrl(KeyCode::Char('g'))], Command::Goto);
+        keymap.bind([K::key(KeyCode::Enter)], Command::InsertNewline);
+        keymap.bind([K::key(KeyCode::Tab)], Command::InsertTab);
+        keymap.bind([K::key(KeyCode::Backspace)], Command::DeleteBackward);
+        keymap.bind([K::key(KeyCode::Delete)], Command::DeleteForward);
+        keymap.bind([K::ctrl(KeyCode::Backspace)], Command::DeleteWordBackward);
+        keymap.bind([K::ctrl(KeyCode::Char('h'))], Command::DeleteWordBackward); // 部分终端中 C-Backspace 会被读取为 C-h.
+        keymap.bind([K::ctrl(KeyCode::Delete)], Command::DeleteWordForward);
+        keymap.bind([K::ctrl(KeyCode::Char('k'))], Command::DeleteLine);
+        keymap.bind([K::ctrl(KeyCode::Char('d'))], Command::DuplicateLine);
+        keymap.bind([K::alt(KeyCode::Up)], Command::MoveLineUp);
+        keymap.bind([K::alt(KeyCode::Down)], Command::MoveLineDown);
+        keymap.bind([K::ctrl(KeyCode::Char('j'))], Command::JoinLines);
+        keymap.bind([K::ctrl(KeyCode::Char('/'))], Command::ToggleComment);
+        keymap.bind([K::ctrl(KeyCode::Char('7'))], Command::ToggleComment); // 大部分终端中 C-/ 会被读取为 C-7.
+        keymap.bind([K::new(KeyCode::Up, KeyModifiers::CONTROL | KeyModifiers::ALT)], Command::AddCursorAbove);
+        keymap.bind([K::new(KeyCode::Down, KeyModifiers::CONTROL | KeyModifiers::ALT)], Command::AddCursorBelow);
+        keymap.bind([K::new(KeyCode::Char('d'), KeyModifiers::CONTROL | KeyModifiers::ALT)], Command::AddNextOccurrence);
+        keymap.bind([K::alt(KeyCode::Char('I'))], Command::SplitSelectionIntoLines);
+        keymap.bind([K::key(KeyCode::Esc)], Command::Cancel);
+        keymap.bind([K::ctrl(KeyCode::Char('e'))], Command::ScrollDown);
+        keymap.bind([K::ctrl(KeyCode::Char('y'))], Command::ScrollUp);
+        keymap.bind([K::ctrl(KeyCode::PageDown)], Command::ScrollHalfPageDown);
+        keymap.bind([K::ctrl(KeyCode::PageUp)], Command::ScrollHalfPageUp);
+        keymap.bind([K::ctrl(KeyCode::Char('l'))], Command::CenterCaret);
+        keymap
+    }
+
+    /// Emacs 风格的按键绑定, 在 [`Keymap::standard`] 的基础上添加 Emacs 的常用按键.
+    ///
+    /// 方向键等非字符按键仍然保留 [`Keymap::standard`] 中的行为, 但 `C-s`, `C-q` 和 `C-o` 被移除,
+    /// `C-p`, `C-k`, `C-d`, `C-e` 和 `C-y` 被替换为 Emacs 中对应的命令.
+    pub fn emacs() -> Keymap {
+        use KeyChord as K;
+        let mut keymap = Keymap::standard();
+        keymap.bindings.remove(&vec![K::ctrl(KeyCode::Char('q'))]);
+        keymap.bindings.remove(&vec![K::ctrl(KeyCode::Char('s'))]);
+        keymap.bindings.remove(&vec![K::ctrl(KeyCode::Char('o'))]);
+        let moves = [
+            (K::ctrl(KeyCode::Char('a')), CaretMove::LineStart),
+            (K::ctrl(KeyCode::Char('e')), CaretMove::LineEnd),
+            (K::ctrl(KeyCode::Char('f')), CaretMove::Right),
+            (K::ctrl(KeyCode::Char('b')), CaretMove::Left),
+            (K::ctrl(KeyCode::Char('n')), CaretMove::Down),
+            (K::ctrl(KeyCode::Char('p')), CaretMove::Up),
+            (K::alt(KeyCode::Char('f')), CaretMove::NextWord),
+            (K::alt(KeyCode::Char('b')), CaretMove::PrevWord),
+            (K::ctrl(KeyCode::Char('v')), CaretMove::PageDown),
+            (K::alt(KeyCode::Char('v')), CaretMove::PageUp),
+            (K::alt(KeyCode::Char('<')), CaretMove::GlobalStart),
+            (K::alt(KeyCode::Char('>')), CaretMove::GlobalEnd),
+        ];
+        for (key, caret_move) in moves {
+            keymap.bind([key], Command::MoveCaret(caret_move));
+        }
+        keymap.bind([K::ctrl(KeyCode::Char('k'))], Command::KillLine);
+        keymap.bind([K::ctrl(KeyCode::Char('d'))], Command::DeleteForward);
+        keymap.bind([K::alt(KeyCode::Char('d'))], Command::DeleteWordForward);
+        keymap.bind([K::alt(KeyCode::Backspace)], Command::DeleteWordBackward);
+        keymap.bind([K::new(KeyCode::Backspace, KeyModifiers::CONTROL | KeyModifiers::SHIFT)], Command::DeleteLine);
+        keymap.bind([K::alt(KeyCode::Char('^'))], Command::JoinLines);
+        keymap.bind([K::alt(KeyCode::Char(';'))], Command::ToggleComment);
+        keymap.bind([K::ctrl(KeyCode::Char('w'))], Command::KillRegion);
+        keymap.bind([K::alt(KeyCode::Char('w'))], Command::CopyRegion);
+        keymap.bind([K::ctrl(KeyCode::Char('y'))], Command::Yank);
+        keymap.bind([K::ctrl(KeyCode::Char(' '))], Command::SetMark);
+        keymap.bind([K::ctrl(KeyCode::Char('@'))], Command::SetMark); // 部分终端中 C-SPC 会被读取为 C-@.
+        keymap.bind([K::ctrl(KeyCode::Char('g'))], Command::Cancel);
+        keymap.bind([K::ctrl(KeyCode::Char('u'))], Command::UniversalArgument);
+        keymap.bind([K::ctrl(KeyCode::Char('m'))], Command::InsertNewline);
+        keymap.bind([K::ctrl(KeyCode::Char('x')), K::ctrl(KeyCode::Char('s'))], Command::Save);
+        keymap.bind([K::ctrl(KeyCode::Char('x')), K::ctrl(KeyCode::Char('c'))], Command::Quit);
+        keymap.bind([K::ctrl(KeyCode::Char('x')), K::ctrl(KeyCode::Char('f'))], Command::Open);
+        keymap.bind([K::alt(KeyCode::Char('x'))], Command::Palette);
+        keymap.bind([K::alt(KeyCode::Char('g')), K::key(KeyCode::Char('g'))], Command::Goto);
+        keymap.bind([K::alt(KeyCode::Char('g')), K::alt(KeyCode::Char('g'))], Command::Goto);
+        keymap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use crate::editor::command::Command;
+    use crate::editor::editarea::CaretMove;
+    use crate::editor::keymap::{parse_key_sequence, KeyChord, KeyLookup, Keymap, KeymapPreset};
+    use crate::error;
+
+    #[test]
+    fn shift_is_normalized() {
+        let event = KeyEvent::new(KeyCode::Char('>'), KeyModifiers::ALT | KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from(event), KeyChord::alt(KeyCode::Char('>')));
+        let event = KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from(event).self_insert_char(), Some('A'));
+    }
+
+    #[test]
+    fn emacs_prefix_chords() {
+        let keymap = Keymap::emacs();
+        let c_x = KeyChord::ctrl(KeyCode::Char('x'));
+        assert_eq!(keymap.lookup(&[c_x]), KeyLookup::Prefix);
+        assert_eq!(keymap.lookup(&[c_x, KeyChord::ctrl(KeyCode::Char('s'))]), KeyLookup::Command(Command::Save));
+        assert_eq!(keymap.lookup(&[c_x, KeyChord::ctrl(KeyCode::Char('c'))]), KeyLookup::Command(Command::Quit));
+        assert_eq!(keymap.lookup(&[c_x, KeyChord::key(KeyCode::Char('z'))]), KeyLookup::Unbound);
+        assert_eq!(keymap.lookup(&[KeyChord::ctrl(KeyCode::Char('s'))]), KeyLookup::Unbound);
+        assert_eq!(
+            keymap.lookup(&[KeyChord::alt(KeyCode::Char('f'))]),
+            KeyLookup::Command(Command::MoveCaret(CaretMove::NextWord))
+        );
+    }
+
+    #[test]
+    fn parse_chords() {
+        assert_eq!("C-x".parse::<KeyChord>().unwrap(), KeyChord::ctrl(KeyCode::Char('x')));
+        assert_eq!("A-f".parse::<KeyChord>().unwrap(), KeyChord::alt(KeyCode::Char('f')));
+        assert_eq!("C--".parse::<KeyChord>().unwrap(), KeyChord::ctrl(KeyCode::Char('-')));
+        assert!("c-m-left".parse::<KeyChord>().is_err());
+        let chord = "C-M-left".parse::<KeyChord>().unwrap();
+        assert_eq!(chord, KeyChord::new(KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::ALT));
+        assert_eq!(chord.to_string(), "C-M-Left");
+        assert_eq!("C-SPC".parse::<KeyChord>().unwrap().to_string(), "C-SPC");
+        assert_eq!("F12".parse::<KeyChord>().unwrap(), KeyChord::key(KeyCode::F(12)));
+        assert!("F25".parse::<KeyChord>().is_err());
+        assert!("X-a".parse::<KeyChord>().is_err());
+        assert!(parse_key_sequence("  ").is_err());
+        assert_eq!(parse_key_sequence("C-x  C-s").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        Keymap::standard().check_conflicts().unwrap();
+        Keymap::emacs().check_conflicts().unwrap();
+    }
+
+    #[test]
+    fn load_from_config() {
+        let keymap = Keymap::from_config_str(r#"
+            preset = "emacs"
+            unbind = ["C-k"]
+            [bindings]
+            "C-c k" = "kill-line"
+            "F2" = "save"
+        "#, KeymapPreset::Standard).unwrap();
+        let c_k = KeyChord::ctrl(KeyCode::Char('k'));
+        assert_eq!(keymap.lookup(&[c_k]), KeyLookup::Unbound);
+        assert_eq!(keymap.lookup(&parse_key_sequence("C-c k").unwrap()), KeyLookup::Command(Command::KillLine));
+        assert_eq!(keymap.lookup(&[KeyChord::key(KeyCode::F(2))]), KeyLookup::Command(Command::Save));
+        assert_eq!(
+            keymap.lookup(&parse_key_sequence("C-x C-c").unwrap()),
+            KeyLookup::Command(Command::Quit)
+        );
+    }
+
+    #[test]
+    fn config_errors() {
+        let load = |content: &str| Keymap::from_config_str(content, KeymapPreset::Emacs);
+        assert!(matches!(
+            load("[bindings]\n\"C-x\" = \"no-such-command\""),
+            Err(error::Error::UnknownCommand(name)) if name == "no-such-command"
+        ));
+        assert!(matches!(
+            load("[bindings]\n\"C-x\" = \"save\""),
+            Err(error::Error::ConflictingBinding { keys, .. }) if keys == "C-x"
+        ));
+        assert!(matches!(
+            load("[bindings]\n\"M-k\" = \"save\"\n\"A-k\" = \"quit\""),
+            Err(error::Error::DuplicateBinding(keys)) if keys == "M-k"
+        ));
+        assert!(matches!(load("[bindings]\n\"Q-k\" = \"save\""), Err(error::Error::InvalidKeyChord(_))));
+        assert!(matches!(load("presets = \"vim\""), Err(error::Error::ConfigParse(_))));
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+
+/// kill ring 最多保存的条目数量.
+const KILL_RING_CAPACITY: usize = 60;
+
+/// 保存被 kill (剪切/复制) 的文本, 最新的条目在最前.
+#[derive(Debug, Default)]
+pub struct KillRing {
+    entries: VecDeque<String>,
+    /// 最新的条目是否为矩形选区中的文本, 见 [`KillRing::push_block`].
+    latest_is_block: bool,
+}
+
+impl KillRing {
+    pub fn new() -> KillRing {
+        KillRing::default()
+    }
+
+    /// 添加新的条目, 超出容量时丢弃最旧的条目.
+    pub fn push(&mut self, text: String) {
+        self.entries.push_front(text);
+        self.entries.truncate(KILL_RING_CAPACITY);
+        self.latest_is_block = false;
+    }
+
+    /// 添加矩形选区中的文本作为新的条目, 各行之间使用 `\n` 连接, 粘贴时作为矩形插入.
+    pub fn push_block(&mut self, text: String) {
+        self.push(text);
+        self.latest_is_block = true;
+    }
+
+    /// 最新的条目是否为矩形选区中的文本.
+    pub fn latest_is_block(&self) -> bool {
+        self.latest_is_block
+    }
+
+    /// 把文本追加到最新的条目末尾, 用于合并连续的 kill 操作.
+    /// 如果 kill ring 为空或者最新的条目为矩形选区中的文本, 那么添加新的条目.
+    pub fn append(&mut self, text: &str) {
+        match self.entries.front_mut() {
+            Some(latest) if !self.latest_is_block => latest.push_str(text),
+            _ => self.push(text.to_owned()),
+        }
+    }
+
+    /// 获取最新的条目.
+    pub fn latest(&self) -> Option<&str> {
+        self.entries.front().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::killring::KillRing;
+
+    #[test]
+    fn push_and_append() {
+        let mut ring = KillRing::new();
+        assert_eq!(ring.latest(), None);
+        ring.append("foo");
+        ring.append("\n");
+        assert_eq!(ring.latest(), Some("foo\n"));
+        ring.push("bar".into());
+        assert_eq!(ring.latest(), Some("bar"));
+        ring.push_block("a\nb".into());
+        assert!(ring.latest_is_block());
+        ring.append("c");
+        assert_eq!(ring.latest(), Some("c"));
+        assert!(!ring.latest_is_block());
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthChar;
+use crate::editor::editarea::Area;
+use crate::editor::Location;
+use crate::editor::settings::Theme;
+use crate::editor::terminal::Terminal;
+use crate::error;
+
+/// 历史中最多保留的消息数量, 超出时丢弃最早的消息.
+const HISTORY_CAPACITY: usize = 100;
+
+/// 消息的严重程度.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Severity {
+    /// 普通的提示, 比如设置项的当前值.
+    Info,
+    /// 操作没有完成, 但是不影响编辑, 比如在 buffer 开头删除字符.
+    Warning,
+    /// 操作失败, 比如保存文件失败.
+    Error,
+}
+
+impl Severity {
+    /// 消息在状态栏中显示的时长, 越严重的消息显示得越久.
+    pub fn timeout(&self) -> Duration {
+        match self {
+            Severity::Info => Duration::from_secs(3),
+            Severity::Warning => Duration::from_secs(5),
+            Severity::Error => Duration::from_secs(10),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// 在状态栏中短暂显示的消息.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Message {
+    pub severity: Severity,
+    pub text: String,
+    /// 消息产生的时间.
+    pub time: Instant,
+}
+
+impl Message {
+    /// 消息不再显示的时间.
+    pub fn deadline(&self) -> Instant {
+        self.time + self.severity.timeout()
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.text)
+    }
+}
+
+/// 消息的历史, 最近的消息在超时之前显示在状态栏中, 全部历史可以通过 `:messages` 查看.
+#[derive(Debug, Default)]
+pub struct Messages {
+    /// 按照产生的顺序排列.
+    history: VecDeque<Message>,
+    /// 最近的消息是否被手动关闭.
+    dismissed: bool,
+}
+
+impl Messages {
+    pub fn new() -> Messages {
+        Messages::default()
+    }
+
+    /// 添加一条消息, 它会替换状态栏中正在显示的消息.
+    pub fn push(&mut self, severity: Severity, text: impl Into<String>) {
+        if self.history.len() == HISTORY_CAPACITY {
+            self.history.pop_front();
+        }
+        self.history.push_back(Message { severity, text: text.into(), time: Instant::now() });
+        self.dismissed = false;
+    }
+
+    /// 把错误作为消息添加, 严重程度见 [`error::Error::severity`].
+    pub fn push_error(&mut self, error: &error::Error) {
+        self.push(error.severity(), error.to_string());
+    }
+
+    /// 在 now 时刻应当显示的消息, 最近的消息超时或者被关闭之后为 None.
+    pub fn current(&self, now: Instant) -> Option<&Message> {
+        self.history.back().filter(|message| !self.dismissed && now < message.deadline())
+    }
+
+    /// 关闭正在显示的消息, 它仍然保留在历史中.
+    pub fn dismiss(&mut self) {
+        self.dismissed = true;
+    }
+
+    /// 全部历史消息, 按照产生的顺序排列.
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &Message> {
+        self.history.iter()
+    }
+
+    /// 在显示区域中打印历史消息, 最近的消息在最下方, 放不下的早期消息不显示, 超出宽度的部分被截断.
+    pub fn print_history_to(&self, terminal: &mut Terminal, area: Area, theme: Theme) -> error::Result<()> {
+        terminal.hide_cursor()?;
+        if self.history.is_empty() {
+            terminal.move_cursor_to(Location::new(area.x(), area.y()))?;
+            terminal.print("No messages.")?;
+        }
+        let shown = self.history.len().min(area.height());
+        let first_row = area.y() + area.height() - shown;
+        for (row, message) in self.history().skip(self.history.len() - shown).enumerate() {
+            let mut width = 0;
+            let line: String = message.to_string()
+                .chars()
+                .take_while(|c| {
+                    width += c.width().unwrap_or(0);
+                    width <= area.width()
+                })
+                .collect();
+            terminal.move_cursor_to(Location::new(area.x(), first_row + row))?;
+            terminal.print_styled(line, theme.message(message.severity))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::editor::message::{Messages, Severity};
+    use crate::error;
+
+    #[test]
+    fn current_and_history() {
+        let mut messages = Messages::new();
+        assert!(messages.current(Instant::now()).is_none());
+        messages.push(Severity::Info, "saved");
+        messages.push_error(&error::Error::DelAtBeginning);
+        let message = messages.current(Instant::now()).unwrap();
+        assert_eq!(message.severity, Severity::Warning);
+        assert_eq!(message.to_string(), "warning: Deleting char at the very beginning of the buffer.");
+        // 超时之后不再显示.
+        let time = message.time;
+        assert!(messages.current(time + Severity::Warning.timeout() - Duration::from_millis(1)).is_some());
+        assert!(messages.current(time + Severity::Warning.timeout()).is_none());
+        messages.push(Severity::Error, "failed");
+        messages.dismiss();
+        assert!(messages.current(Instant::now()).is_none());
+        let texts: Vec<&str> = messages.history().map(|message| message.text.as_str()).collect();
+        assert_eq!(texts[0], "saved");
+        assert_eq!(texts[2], "failed");
+        for i in 0..200 {
+            messages.push(Severity::Info, i.to_string());
+        }
+        assert_eq!(messages.history().count(), 100);
+        assert_eq!(messages.history().next().unwrap().text, "100");
+    }
+}
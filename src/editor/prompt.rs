@@ -0,0 +1,254 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use unicode_width::UnicodeWidthStr;
+use crate::editor::keymap::KeyChord;
+
+/// 输入提示的用途, 决定了输入提交之后的处理方式.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum PromptKind {
+    /// 命令面板, 输入命令和参数, 比如 `w file.txt`.
+    Command,
+    /// 跳转位置, 格式见 [`GotoTarget`](crate::editor::editarea::GotoTarget).
+    Goto,
+    /// 关闭有未保存的修改的 buffer 之前询问是保存, 丢弃修改还是取消.
+    UnsavedChanges,
+}
+
+impl PromptKind {
+    /// 是否按下一个字符键就提交这个字符, 不需要按 Enter, 用于回答选择题.
+    pub fn answers_with_key(&self) -> bool {
+        matches!(self, PromptKind::UnsavedChanges)
+    }
+}
+
+/// [`Prompt::handle_key`] 的处理结果.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum PromptResult {
+    /// 继续输入.
+    Pending,
+    /// 提交输入的内容.
+    Submit(String),
+    /// 取消输入.
+    Cancel,
+}
+
+/// 连续按下 Tab 时的补全状态.
+#[derive(Debug)]
+struct Completion {
+    candidates: Vec<String>,
+    /// 当前显示的候选项, None 表示还没有开始循环候选项.
+    index: Option<usize>,
+}
+
+/// 在状态栏中显示的单行输入提示.
+#[derive(Debug)]
+pub struct Prompt {
+    kind: PromptKind,
+    label: String,
+    input: String,
+    /// 输入光标在 input 中的字节位置.
+    cursor: usize,
+    completion: Option<Completion>,
+}
+
+impl Prompt {
+    pub fn new(kind: PromptKind, label: impl Into<String>) -> Prompt {
+        Prompt {
+            kind,
+            label: label.into(),
+            input: String::new(),
+            cursor: 0,
+            completion: None,
+        }
+    }
+
+    /// 设置初始输入内容, 输入光标在内容末尾.
+    pub fn with_input(mut self, input: impl Into<String>) -> Prompt {
+        self.input = input.into();
+        self.cursor = self.input.len();
+        self
+    }
+
+    pub fn kind(&self) -> PromptKind {
+        self.kind
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// 输入光标之前的内容的显示宽度.
+    pub fn cursor_width(&self) -> usize {
+        self.input[..self.cursor].width()
+    }
+
+    /// 处理按键.
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: 按键.
+    /// * `complete`: 补全函数, 参数为当前的输入, 返回补全之后完整的输入的候选项.
+    pub fn handle_key(&mut self, key: KeyChord, complete: impl FnOnce(&str) -> Vec<String>) -> PromptResult {
+        if key.code != KeyCode::Tab {
+            self.completion = None;
+        }
+        let ctrl = key.modifiers == KeyModifiers::CONTROL;
+        match key.code {
+            KeyCode::Esc => return PromptResult::Cancel,
+            KeyCode::Char('g') | KeyCode::Char('c') if ctrl => return PromptResult::Cancel,
+            _ if self.kind.answers_with_key() => {
+                return key.self_insert_char().map_or(PromptResult::Pending, |ch| PromptResult::Submit(ch.to_string()));
+            }
+            KeyCode::Enter => return PromptResult::Submit(self.input.clone()),
+            KeyCode::Tab => self.complete(complete),
+            KeyCode::Backspace => {
+                if let Some(ch) = self.input[..self.cursor].chars().next_back() {
+                    self.cursor -= ch.len_utf8();
+                    self.input.remove(self.cursor);
+                }
+            }
+            KeyCode::Delete => {
+                if self.cursor < self.input.len() {
+                    self.input.remove(self.cursor);
+                }
+            }
+            KeyCode::Left => {
+                if let Some(ch) = self.input[..self.cursor].chars().next_back() {
+                    self.cursor -= ch.len_utf8();
+                }
+            }
+            KeyCode::Right => {
+                if let Some(ch) = self.input[self.cursor..].chars().next() {
+                    self.cursor += ch.len_utf8();
+                }
+            }
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::End => self.cursor = self.input.len(),
+            KeyCode::Char('e') if ctrl => self.cursor = self.input.len(),
+            _ => {
+                if let Some(ch) = key.self_insert_char() {
+                    self.input.insert(self.cursor, ch);
+                    self.cursor += ch.len_utf8();
+                }
+            }
+        }
+        PromptResult::Pending
+    }
+
+    /// 补全输入.
+    ///
+    /// 第一次按下 Tab 时补全到所有候选项的最长公共前缀, 之后连续按下 Tab 会依次使用各个候选项.
+    fn complete(&mut self, complete: impl FnOnce(&str) -> Vec<String>) {
+        let completion = self.completion.get_or_insert_with(|| Completion {
+            candidates: complete(&self.input),
+            index: None,
+        });
+        if completion.candidates.is_empty() {
+            return;
+        }
+        let common = longest_common_prefix(&completion.candidates);
+        let input = match completion.index {
+            None if common.len() > self.input.len() || completion.candidates.len() == 1 => {
+                common.to_owned()
+            }
+            None => {
+                completion.index = Some(0);
+                completion.candidates[0].clone()
+            }
+            Some(index) => {
+                let index = (index + 1) % completion.candidates.len();
+                completion.index = Some(index);
+                completion.candidates[index].clone()
+            }
+        };
+        self.input = input;
+        self.cursor = self.input.len();
+    }
+}
+
+/// 所有字符串的最长公共前缀.
+fn longest_common_prefix(strings: &[String]) -> &str {
+    let Some(first) = strings.first() else {
+        return "";
+    };
+    let mut len = first.len();
+    for s in &strings[1..] {
+        len = first.char_indices()
+            .zip(s.chars())
+            .take_while(|((i, a), b)| *i < len && a == b)
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0);
+    }
+    &first[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use crate::editor::keymap::KeyChord;
+    use crate::editor::prompt::{Prompt, PromptKind, PromptResult};
+
+    fn type_str(prompt: &mut Prompt, s: &str) {
+        for ch in s.chars() {
+            prompt.handle_key(KeyChord::key(KeyCode::Char(ch)), |_| unreachable!());
+        }
+    }
+
+    #[test]
+    fn edit_and_submit() {
+        let mut prompt = Prompt::new(PromptKind::Command, ":");
+        type_str(&mut prompt, "gto");
+        prompt.handle_key(KeyChord::key(KeyCode::Left), |_| unreachable!());
+        prompt.handle_key(KeyChord::key(KeyCode::Left), |_| unreachable!());
+        type_str(&mut prompt, "o");
+        assert_eq!(prompt.cursor_width(), 2);
+        assert_eq!(
+            prompt.handle_key(KeyChord::key(KeyCode::Enter), |_| unreachable!()),
+            PromptResult::Submit("goto".into())
+        );
+        assert_eq!(
+            prompt.handle_key(KeyChord::key(KeyCode::Esc), |_| unreachable!()),
+            PromptResult::Cancel
+        );
+    }
+
+    #[test]
+    fn answer_with_key() {
+        let mut prompt = Prompt::new(PromptKind::UnsavedChanges, "Save changes? ");
+        assert_eq!(prompt.handle_key(KeyChord::key(KeyCode::Enter), |_| unreachable!()), PromptResult::Pending);
+        assert_eq!(
+            prompt.handle_key(KeyChord::key(KeyCode::Char('d')), |_| unreachable!()),
+            PromptResult::Submit("d".into())
+        );
+        assert_eq!(prompt.input(), "");
+        assert_eq!(
+            prompt.handle_key(KeyChord::ctrl(KeyCode::Char('g')), |_| unreachable!()),
+            PromptResult::Cancel
+        );
+    }
+
+    #[test]
+    fn tab_completion() {
+        let candidates = || vec!["save".to_owned(), "set".to_owned()];
+        let tab = KeyChord::key(KeyCode::Tab);
+        let mut prompt = Prompt::new(PromptKind::Command, ":").with_input("s");
+        prompt.handle_key(tab, |input| {
+            assert_eq!(input, "s");
+            candidates()
+        });
+        assert_eq!(prompt.input(), "save");
+        prompt.handle_key(tab, |_| unreachable!());
+        assert_eq!(prompt.input(), "set");
+        prompt.handle_key(tab, |_| unreachable!());
+        assert_eq!(prompt.input(), "save");
+
+        let mut prompt = Prompt::new(PromptKind::Command, ":").with_input("ki");
+        prompt.handle_key(tab, |_| vec!["kill-line".to_owned(), "kill-region".to_owned()]);
+        assert_eq!(prompt.input(), "kill-");
+    }
+}
@@ -0,0 +1,266 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use crate::editor::keymap::KeyChord;
+use crate::editor::{BufferLoadConfig, Editor, EditorBuildConfig, Location, Size, Terminal, VirtualTerminal};
+
+/// 按键回放脚本所在的目录, 每个 `.keys` 文件是一个脚本.
+const SCRIPTS_DIR: &str = "replay";
+
+/// 打开文件时虚拟终端的默认尺寸.
+const DEFAULT_SIZE: Size = Size { width: 40, height: 10 };
+
+/// 按键回放脚本中的一步.
+///
+/// 脚本每行一步, 空行和 `#` 开头的行被忽略:
+///
+/// - `size <width> <height>`: 设置虚拟终端的尺寸, 在 `open` 之后使用时相当于改变终端的尺寸.
+/// - `open <file>`: 在虚拟终端中打开文件进行编辑, 之后的步骤都作用于此文件, 使用默认的按键绑定和设置.
+/// - `keys <key sequence>`: 依次按下按键, 写法见 [`KeyChord`], 在按键之后加上 `*N` 表示重复 N 次, 比如 `Right*3`.
+/// - `type <text>`: 依次输入 text 中的字符, text 为 `type ` 之后的全部内容.
+/// - `expect ...`: 检查编辑器的状态, 见 [`Expectation`].
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum Step {
+    Size(Size),
+    Open(PathBuf),
+    Keys(Vec<KeyChord>),
+    Type(String),
+    Expect(Expectation),
+}
+
+/// 对编辑器状态的检查, buffer 中的位置从 1 开始计数 (同状态栏), 屏幕中的位置从 0 开始计数.
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum Expectation {
+    /// `expect line <n> <text>`: buffer 第 n 行的内容.
+    Line(usize, String),
+    /// `expect lines <n>`: buffer 的总行数.
+    Lines(usize),
+    /// `expect caret <line>:<column>`: caret 所在的行和列, 列按照字符计数.
+    Caret(usize, usize),
+    /// `expect row <n> <text>`: 屏幕第 n 行显示的文本, 不包括行末的空格.
+    Row(usize, String),
+    /// `expect cursor <row>:<column>`: 终端 cursor 所在的格子, 列按照显示宽度计数.
+    Cursor(usize, usize),
+}
+
+/// 解析 `a:b` 形式的一对数字.
+fn parse_pair(s: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("expected `<number>:<number>`, found `{s}`");
+    let (a, b) = s.split_once(':').ok_or_else(invalid)?;
+    Ok((a.parse().map_err(|_| invalid())?, b.parse().map_err(|_| invalid())?))
+}
+
+fn parse_number(s: &str) -> Result<usize, String> {
+    s.parse().map_err(|_| format!("expected a number, found `{s}`"))
+}
+
+/// 解析按键序列, 按键之后可以带有 `*N` 表示重复.
+fn parse_keys(s: &str) -> Result<Vec<KeyChord>, String> {
+    let mut keys = Vec::new();
+    for word in s.split_whitespace() {
+        let (chord, count) = match word.rsplit_once('*') {
+            Some((chord, count)) if !chord.is_empty() => (chord, parse_number(count)?),
+            _ => (word, 1),
+        };
+        let chord = KeyChord::from_str(chord).map_err(|e| e.to_string())?;
+        keys.extend(std::iter::repeat_n(chord, count));
+    }
+    if keys.is_empty() {
+        return Err("expected at least one key".to_owned());
+    }
+    Ok(keys)
+}
+
+impl FromStr for Step {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Step, String> {
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match name {
+            "size" => {
+                let (width, height) = rest.split_once(' ').ok_or("expected `size <width> <height>`")?;
+                Ok(Step::Size(Size::new(parse_number(width)?, parse_number(height.trim())?)))
+            }
+            "open" if !rest.is_empty() => Ok(Step::Open(PathBuf::from(rest))),
+            "keys" => parse_keys(rest).map(Step::Keys),
+            "type" => Ok(Step::Type(rest.to_owned())),
+            "expect" => {
+                let (what, rest) = rest.split_once(' ').ok_or("expected `expect <what> <value>`")?;
+                let expectation = match what {
+                    "line" | "row" => {
+                        let (n, text) = rest.split_once(' ').unwrap_or((rest, ""));
+                        let n = parse_number(n)?;
+                        if what == "line" { Expectation::Line(n, text.to_owned()) } else { Expectation::Row(n, text.to_owned()) }
+                    }
+                    "lines" => Expectation::Lines(parse_number(rest)?),
+                    "caret" => parse_pair(rest).map(|(line, column)| Expectation::Caret(line, column))?,
+                    "cursor" => parse_pair(rest).map(|(row, column)| Expectation::Cursor(row, column))?,
+                    _ => return Err(format!("unknown expectation `{what}`")),
+                };
+                Ok(Step::Expect(expectation))
+            }
+            _ => Err(format!("unknown step `{line}`")),
+        }
+    }
+}
+
+/// 解析脚本, 返回每一步及其所在的行号.
+fn parse_script(script: &str) -> Result<Vec<(usize, Step)>, String> {
+    script.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(idx, line)| line.parse().map(|step| (idx + 1, step)).map_err(|e| format!("line {}: {e}", idx + 1)))
+        .collect()
+}
+
+/// 正在回放的脚本的状态.
+struct Replay {
+    size: Size,
+    /// `open` 之后才存在.
+    session: Option<(Editor, VirtualTerminal)>,
+}
+
+impl Replay {
+    fn new() -> Replay {
+        Replay { size: DEFAULT_SIZE, session: None }
+    }
+
+    fn session(&mut self) -> Result<&mut (Editor, VirtualTerminal), String> {
+        self.session.as_mut().ok_or_else(|| "no file is opened, use `open <file>` first".to_owned())
+    }
+
+    fn run(&mut self, step: &Step) -> Result<(), String> {
+        match step {
+            Step::Size(size) => {
+                self.size = *size;
+                if let Some((_, terminal)) = &self.session {
+                    terminal.resize(*size);
+                }
+            }
+            Step::Open(path) => {
+                let config = EditorBuildConfig {
+                    edit_text_config: BufferLoadConfig::File(path),
+                    ..Default::default()
+                };
+                let terminal = VirtualTerminal::new(self.size);
+                let editor = Editor::build_on(&config, Terminal::with(terminal.clone(), terminal.clone()))
+                    .map_err(|e| format!("failed to open {path:?}: {e}"))?;
+                self.session = Some((editor, terminal));
+            }
+            Step::Keys(keys) => {
+                let (_, terminal) = self.session()?;
+                keys.iter().for_each(|key| terminal.push_key(key.code, key.modifiers));
+            }
+            Step::Type(text) => self.session()?.1.type_str(text),
+            Step::Expect(expectation) => {
+                let (editor, terminal) = self.session()?;
+                editor.run_pending().map_err(|e| e.to_string())?;
+                check(editor, terminal, expectation)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 检查编辑器的状态是否符合预期.
+fn check(editor: &Editor, terminal: &VirtualTerminal, expectation: &Expectation) -> Result<(), String> {
+    let buffer = editor.buffer();
+    // (检查的内容, 预期的值, 实际的值)
+    let (what, expected, actual) = match expectation {
+        Expectation::Line(n, text) => {
+            let line = n.checked_sub(1).and_then(|y| buffer.get(y));
+            (format!("line {n}"), format!("`{text}`"), line.map_or("no such line".to_owned(), |line| format!("`{line}`")))
+        }
+        Expectation::Lines(n) => ("lines".to_owned(), n.to_string(), buffer.lines_num().to_string()),
+        Expectation::Caret(line, column) => {
+            let caret = buffer.caret();
+            let x = buffer.get(caret.y).map_or(0, |text| text[..caret.x].chars().count());
+            ("caret".to_owned(), format!("{line}:{column}"), format!("{}:{}", caret.y + 1, x + 1))
+        }
+        Expectation::Row(n, text) => (format!("row {n}"), format!("`{text}`"), format!("`{}`", terminal.line(*n))),
+        Expectation::Cursor(row, column) => (
+            "cursor".to_owned(),
+            format!("{row}:{column}"),
+            terminal.cursor().map_or("hidden".to_owned(), |Location { x, y }| format!("{y}:{x}")),
+        ),
+    };
+    if expected == actual {
+        Ok(())
+    } else {
+        let mut message = format!("expected {what} {expected}, found {actual}");
+        if let Expectation::Row(..) | Expectation::Cursor(..) = expectation {
+            // 附上屏幕的内容, 方便编写预期.
+            terminal.lines().iter().enumerate().for_each(|(y, line)| message += &format!("\n{y:>3}|{line}"));
+        }
+        Err(message)
+    }
+}
+
+/// 回放脚本, 错误信息包含出错的步骤所在的行号.
+fn replay(script: &str) -> Result<(), String> {
+    let mut replay = Replay::new();
+    for (line, step) in parse_script(script)? {
+        replay.run(&step).map_err(|e| format!("line {line}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// [`SCRIPTS_DIR`] 中的全部脚本, 按照文件名排序.
+fn scripts() -> Vec<PathBuf> {
+    let mut scripts: Vec<PathBuf> = fs::read_dir(SCRIPTS_DIR).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "keys"))
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+fn replay_file(path: &Path) -> Result<(), String> {
+    let script = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    replay(&script)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::keymap::KeyChord;
+    use crate::editor::replay::{parse_script, replay, replay_file, scripts, Expectation, Step};
+    use crossterm::event::KeyCode;
+
+    #[test]
+    fn parse() {
+        let steps = parse_script("# comment\n\nkeys C-Home Right*2\ntype a b\nexpect line 2 \nexpect cursor 0:5").unwrap();
+        let right = KeyChord::key(KeyCode::Right);
+        assert_eq!(steps[0], (3, Step::Keys(vec![KeyChord::ctrl(KeyCode::Home), right, right])));
+        assert_eq!(steps[1], (4, Step::Type("a b".to_owned())));
+        assert_eq!(steps[2], (5, Step::Expect(Expectation::Line(2, String::new()))));
+        assert_eq!(steps[3], (6, Step::Expect(Expectation::Cursor(0, 5))));
+        assert_eq!(parse_script("keys Foo").unwrap_err(), "line 1: Invalid key chord `Foo`.");
+        assert_eq!(parse_script("expect caret 1").unwrap_err(), "line 1: expected `<number>:<number>`, found `1`");
+        assert!(parse_script("jump 3").is_err());
+    }
+
+    #[test]
+    fn failures() {
+        assert_eq!(replay("keys Right").unwrap_err(), "line 1: no file is opened, use `open <file>` first");
+        assert_eq!(
+            replay("open example-single-line.txt\nexpect line 1 Hello").unwrap_err(),
+            "line 2: expected line 1 `Hello`, found `Hello World`"
+        );
+        assert_eq!(
+            replay("open example-single-line.txt\nexpect caret 1:1").unwrap_err(),
+            "line 2: expected caret 1:1, found 1:12"
+        );
+    }
+
+    /// 回放 `replay` 目录中的全部脚本.
+    #[test]
+    fn replay_scripts() {
+        let scripts = scripts();
+        assert!(!scripts.is_empty());
+        for script in scripts {
+            if let Err(e) = replay_file(&script) {
+                panic!("{}: {e}", script.display());
+            }
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use serde::Deserialize;
+use crate::editor::filetype::Filetype;
+use crate::editor::message::Severity;
+use crate::editor::terminal::{Color, Style};
+use crate::error;
+
+/// 内置的配色方案.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// 使用终端的默认颜色.
+    #[default]
+    Default,
+    /// 适合深色背景的终端.
+    Dark,
+    /// 适合浅色背景的终端.
+    Light,
+}
+
+/// 所有配色方案及其名称.
+const THEMES: &[(&str, Theme)] = &[
+    ("default", Theme::Default),
+    ("dark", Theme::Dark),
+    ("light", Theme::Light),
+];
+
+impl Theme {
+    /// 状态栏的样式.
+    pub fn status_bar(&self) -> Style {
+        match self {
+            Theme::Default => Style::default(),
+            Theme::Dark => Style::new(Some(Color::White), Some(Color::DarkBlue)),
+            Theme::Light => Style::new(Some(Color::Black), Some(Color::Grey)),
+        }
+    }
+
+    /// 状态栏中的消息和消息历史的样式, 由消息的严重程度决定.
+    pub fn message(&self, severity: Severity) -> Style {
+        match (self, severity) {
+            (_, Severity::Info) => self.status_bar(),
+            (Theme::Default, _) => Style { reverse: true, ..Style::default() },
+            (Theme::Dark | Theme::Light, Severity::Warning) => Style::new(Some(Color::Black), Some(Color::Yellow)),
+            (Theme::Dark | Theme::Light, Severity::Error) => Style::new(Some(Color::White), Some(Color::DarkRed)),
+        }
+    }
+
+    /// 与 caret 旁边的括号匹配的括号对的样式.
+    pub fn matching_bracket(&self) -> Style {
+        match self {
+            Theme::Default => Style { reverse: true, ..Style::default() },
+            Theme::Dark => Style::new(Some(Color::Black), Some(Color::DarkCyan)),
+            Theme::Light => Style::new(None, Some(Color::Cyan)),
+        }
+    }
+
+    /// 主光标之外的其他光标的样式, 见 [`Buffer::cursors`](crate::editor::buffer::Buffer::cursors).
+    pub fn secondary_cursor(&self) -> Style {
+        match self {
+            Theme::Default => Style { reverse: true, ..Style::default() },
+            Theme::Dark => Style::new(Some(Color::Black), Some(Color::White)),
+            Theme::Light => Style::new(Some(Color::White), Some(Color::Black)),
+        }
+    }
+
+    /// 选区的样式.
+    pub fn selection(&self) -> Style {
+        match self {
+            Theme::Default => Style { reverse: true, ..Style::default() },
+            Theme::Dark => Style::new(None, Some(Color::DarkGrey)),
+            Theme::Light => Style::new(None, Some(Color::Grey)),
+        }
+    }
+
+    /// 行号的样式.
+    pub fn line_number(&self) -> Style {
+        match self {
+            Theme::Default => Style::default(),
+            Theme::Dark => Style::new(Some(Color::DarkGrey), None),
+            Theme::Light => Style::new(Some(Color::Grey), None),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = THEMES.iter().find(|(_, theme)| theme == self).unwrap(); // THEMES 包含了所有的配色方案.
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Theme, ()> {
+        THEMES.iter().find(|(name, _)| *name == s).map(|(_, theme)| *theme).ok_or(())
+    }
+}
+
+/// 缩进使用的字符.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentStyle {
+    /// 使用 [`Settings::indent_size`] 个空格缩进.
+    #[default]
+    Space,
+    /// 使用制表符缩进.
+    Tab,
+}
+
+impl fmt::Display for IndentStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndentStyle::Space => write!(f, "space"),
+            IndentStyle::Tab => write!(f, "tab"),
+        }
+    }
+}
+
+impl FromStr for IndentStyle {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<IndentStyle, ()> {
+        match s {
+            "space" => Ok(IndentStyle::Space),
+            "tab" => Ok(IndentStyle::Tab),
+            _ => Err(()),
+        }
+    }
+}
+
+/// 定义所有的设置项, 生成 [`Settings`], [`SettingsLayer`] 和 [`OPTIONS`].
+///
+/// 每个设置项的格式为 `字段名: 类型 = 默认值, "设置项名称";`,
+/// 设置项名称用于 [`Command::Set`](crate::editor::command::Command::Set) 和配置文件.
+macro_rules! settings {
+    ($($(#[doc = $doc:literal])* $field:ident: $ty:ty = $default:expr, $name:literal;)*) => {
+        /// 编辑器的设置, 由默认值依次经过各层 [`SettingsLayer`] 的覆盖得到, 见 [`SettingsConfig::resolve`].
+        #[derive(Debug, Eq, PartialEq, Copy, Clone)]
+        pub struct Settings {
+            $($(#[doc = $doc])* pub $field: $ty,)*
+        }
+
+        impl Default for Settings {
+            fn default() -> Self {
+                Settings { $($field: $default,)* }
+            }
+        }
+
+        /// 一层设置的覆盖, 为 None 的设置项不覆盖下层的值.
+        #[derive(Debug, Eq, PartialEq, Clone, Default, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        pub struct SettingsLayer {
+            $(#[serde(rename = $name)] pub $field: Option<$ty>,)*
+        }
+
+        /// 所有设置项的名称.
+        pub const OPTIONS: &[&str] = &[$($name),*];
+
+        impl Settings {
+            /// 使用 layer 中设置了的项覆盖当前的值.
+            pub fn apply(&mut self, layer: &SettingsLayer) {
+                $(if let Some(value) = layer.$field {
+                    self.$field = value;
+                })*
+            }
+
+            /// 获取设置项的值的文本表示, 设置项不存在时返回 None.
+            pub fn get(&self, name: &str) -> Option<String> {
+                match name {
+                    $($name => Some(self.$field.to_string()),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl SettingsLayer {
+            /// 通过名称修改设置项, 值为文本表示.
+            ///
+            /// # Errors
+            ///
+            /// - [`error::Error::UnknownOption`]: 设置项不存在, 见 [`OPTIONS`].
+            /// - [`error::Error::InvalidOptionValue`]: 值的格式错误或者值不合法, 此时 layer 不会被修改.
+            pub fn set(&mut self, name: &str, value: &str) -> error::Result<()> {
+                let invalid = || error::Error::InvalidOptionValue { option: name.to_owned(), value: value.to_owned() };
+                let mut layer = self.clone();
+                match name {
+                    $($name => layer.$field = Some(value.trim().parse().map_err(|_| invalid())?),)*
+                    _ => return Err(error::Error::UnknownOption(name.to_owned())),
+                }
+                layer.validate()?;
+                *self = layer;
+                Ok(())
+            }
+        }
+    };
+}
+
+settings! {
+    /// 制表位的宽度, 即制表符的显示宽度.
+    tab_width: usize = 4, "tabwidth";
+    /// tab 键插入的缩进使用的字符, 见 [`IndentStyle`].
+    indent_style: IndentStyle = IndentStyle::Space, "indentstyle";
+    /// 使用空格缩进时, tab 键插入的空格数量.
+    indent_size: usize = 4, "indentsize";
+    /// caret 上下移动时, 显示区域发生滚动会尽可能不会让 caret 直接贴住可显示范围的边缘, 而是保留一定的可视行数预览后/前几行.
+    /// 此设置为 caret 与显示区域上下边缘保留的行数.
+    ///
+    /// 如果可显示范围的高度不足 `2 * vertical_padding`, 那么此设置无效.
+    vertical_padding: usize = 3, "vpadding";
+    /// caret 与显示区域左右边缘保留的列数, 基本同理于 [`Settings::vertical_padding`].
+    horizontal_padding: usize = 5, "hpadding";
+    /// 状态栏中的内容与左右两端的距离.
+    status_padding: usize = 2, "statuspadding";
+    /// 超出显示宽度的行是否折行显示, 折行显示时不进行水平滚动.
+    wrap: bool = false, "wrap";
+    /// 是否在文本左侧显示行号.
+    line_numbers: bool = false, "linenumbers";
+    /// 输入左括号和引号时是否自动插入对应的右括号和引号.
+    auto_close: bool = false, "autoclose";
+    /// 单词移动时是否在驼峰命名和下划线命名的分界处停下, 见 [`word_starts`](crate::editor::word::word_starts).
+    subword: bool = false, "subword";
+    /// 配色方案, 见 [`Theme`].
+    theme: Theme = Theme::Default, "theme";
+}
+
+impl SettingsLayer {
+    /// 检查设置的值是否合法.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::InvalidOptionValue`]
+    fn validate(&self) -> error::Result<()> {
+        for (option, value) in [("tabwidth", self.tab_width), ("indentsize", self.indent_size)] {
+            if value == Some(0) {
+                return Err(error::Error::InvalidOptionValue { option: option.to_owned(), value: "0".to_owned() });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 设置的配置文件 (`config.toml`) 的内容.
+///
+/// 格式如下:
+///
+/// ```toml
+/// [global]
+/// tabwidth = 4
+/// linenumbers = true
+/// theme = "dark"
+///
+/// [filetype.makefile]
+/// tabwidth = 8
+/// ```
+///
+/// 设置项名称见 [`OPTIONS`], 文件类型名称见 [`FILETYPES`](crate::editor::filetype::FILETYPES).
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsConfig {
+    /// 对所有文件生效的设置.
+    #[serde(default)]
+    global: SettingsLayer,
+    /// 按照文件类型覆盖的设置.
+    #[serde(default)]
+    filetype: HashMap<String, SettingsLayer>,
+}
+
+impl SettingsConfig {
+    /// 从配置文件中加载设置.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::ConfigFile`]: 包含文件路径和具体的错误, 具体错误见 [`SettingsConfig::from_config_str`].
+    pub fn load(path: impl AsRef<Path>) -> error::Result<SettingsConfig> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(error::Error::from)
+            .and_then(|content| SettingsConfig::from_config_str(&content))
+            .map_err(|e| error::Error::ConfigFile { path: path.to_path_buf(), source: Box::new(e) })
+    }
+
+    /// 从配置文件内容中解析设置.
+    ///
+    /// # Errors
+    ///
+    /// - [`error::Error::ConfigParse`]: 配置内容不是有效的 TOML, 或者包含未知的设置项, 或者值的类型错误.
+    /// - [`error::Error::InvalidOptionValue`]: 设置的值不合法, 比如 `tabwidth = 0`.
+    /// - [`error::Error::UnknownFiletype`]: `[filetype.<name>]` 中的文件类型不存在.
+    pub fn from_config_str(content: &str) -> error::Result<SettingsConfig> {
+        let config: SettingsConfig = toml::from_str(content)?;
+        config.global.validate()?;
+        for (name, layer) in &config.filetype {
+            if Filetype::by_name(name).is_none() {
+                return Err(error::Error::UnknownFiletype(name.clone()));
+            }
+            layer.validate()?;
+        }
+        Ok(config)
+    }
+
+    /// 计算最终生效的设置, 优先级从低到高依次为: 默认值, 全局设置, 文件类型的设置, 文件自身的设置, 运行时修改的设置.
+    ///
+    /// # Arguments
+    ///
+    /// * `filetype`: 正在编辑的文件的类型名称, 未知类型时为 None.
+    /// * `file`: 针对正在编辑的文件的设置, 比如 `.editorconfig` 中的设置.
+    /// * `runtime`: 运行时通过 [`Command::Set`](crate::editor::command::Command::Set) 修改的设置.
+    pub fn resolve(&self, filetype: Option<&str>, file: &SettingsLayer, runtime: &SettingsLayer) -> Settings {
+        let mut settings = Settings::default();
+        settings.apply(&self.global);
+        if let Some(layer) = filetype.and_then(|name| self.filetype.get(name)) {
+            settings.apply(layer);
+        }
+        settings.apply(file);
+        settings.apply(runtime);
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::settings::{IndentStyle, Settings, SettingsConfig, SettingsLayer, Theme};
+
+    #[test]
+    fn resolve_layers() {
+        let config = SettingsConfig::from_config_str(r#"
+            [global]
+            tabwidth = 2
+            linenumbers = true
+            theme = "dark"
+
+            [filetype.makefile]
+            tabwidth = 8
+            wrap = true
+        "#).unwrap();
+        let settings = config.resolve(None, &SettingsLayer::default(), &SettingsLayer::default());
+        assert_eq!(settings.tab_width, 2);
+        assert!(settings.line_numbers);
+        assert_eq!(settings.theme, Theme::Dark);
+        assert_eq!(settings.vertical_padding, Settings::default().vertical_padding);
+
+        let mut file = SettingsLayer::default();
+        file.set("indentstyle", "tab").unwrap();
+        let mut runtime = SettingsLayer::default();
+        runtime.set("wrap", "false").unwrap();
+        let settings = config.resolve(Some("makefile"), &file, &runtime);
+        assert_eq!(settings.tab_width, 8);
+        assert_eq!(settings.indent_style, IndentStyle::Tab);
+        assert!(!settings.wrap);
+        assert_eq!(settings.get("tabwidth").unwrap(), "8");
+        assert_eq!(settings.get("theme").unwrap(), "dark");
+    }
+
+    #[test]
+    fn runtime_set() {
+        let mut layer = SettingsLayer::default();
+        layer.set("tabwidth", " 3").unwrap();
+        layer.set("theme", "light").unwrap();
+        assert_eq!(layer.tab_width, Some(3));
+        assert_eq!(layer.theme, Some(Theme::Light));
+        assert!(layer.set("tabwidth", "0").is_err());
+        assert!(layer.set("indentsize", "0").is_err());
+        assert!(layer.set("indentstyle", "tabs").is_err());
+        assert!(layer.set("tabwidth", "x").is_err());
+        assert!(layer.set("theme", "no-such-theme").is_err());
+        assert!(layer.set("no-such-option", "1").is_err());
+        assert_eq!(layer.tab_width, Some(3));
+    }
+
+    #[test]
+    fn config_errors() {
+        assert!(SettingsConfig::from_config_str("[global]\nno-such-option = 1").is_err());
+        assert!(SettingsConfig::from_config_str("[global]\ntabwidth = 0").is_err());
+        assert!(SettingsConfig::from_config_str("[global]\ntabwidth = \"4\"").is_err());
+        assert!(SettingsConfig::from_config_str("[filetype.no-such-type]\ntabwidth = 4").is_err());
+        assert!(SettingsConfig::from_config_str("[editor]").is_err());
+    }
+}
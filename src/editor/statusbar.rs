@@ -1,11 +1,10 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 use crate::editor::{Location, Printable};
 use crate::editor::editarea::Area;
-use crate::editor::terminal::Terminal;
+use crate::editor::prompt::Prompt;
+use crate::editor::terminal::{Style, Terminal};
 use crate::error;
 
-/// 在状态条左右有多长的空白.
-pub const HORIZONTAL_PADDING: usize = 2;
-
 /// [`StatusBar`] 中文字的显示位置.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Packing {
@@ -25,18 +24,55 @@ pub enum Packing {
     Right(usize, usize),
 }
 
+/// 状态栏中的一段内容, 比如文件名和 caret 位置.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Segment {
+    pub text: String,
+    pub packing: Packing,
+}
+
+impl Segment {
+    pub fn new(text: impl Into<String>, packing: Packing) -> Segment {
+        Segment { text: text.into(), packing }
+    }
+
+    /// 包括边距在内的显示宽度.
+    fn width(&self) -> usize {
+        let (l_padding, r_padding) = match self.packing {
+            Packing::Center => (0, 0),
+            Packing::Left(l, r) | Packing::Right(l, r) => (l, r),
+        };
+        l_padding + self.text.width() + r_padding
+    }
+
+    /// 包括边距在内的显示文本, 边距用空格填充.
+    fn padded_text(&self) -> String {
+        match self.packing {
+            Packing::Center => self.text.clone(),
+            Packing::Left(l, r) | Packing::Right(l, r) => format!("{}{}{}", " ".repeat(l), self.text, " ".repeat(r)),
+        }
+    }
+}
+
 /// 状态显示条, 显示区域高度只有一行.
+///
+/// 状态栏由多段内容 ([`Segment`]) 组成, 相同 [`Packing`] 的内容按照顺序依次排列:
+/// 靠左的内容从左端开始排列, 靠右的内容排列之后最后一段靠在右端, 居中的内容和提示消息排列之后居中显示.
+/// 显示宽度不足时提示消息优先显示, 然后是靠右的内容 (比如 caret 的位置), 最后是靠左的内容.
 #[derive(Debug)]
 pub struct StatusBar {
     /// 显示区域在终端中的行序号.
     display_line: usize,
     /// 显示区域在终端行中的起始位置.
     display_start: usize,
-    /// 显示区域的水平宽度, 不是实际字符占据的宽度, 还要考虑 HORIZONTAL_PADDING (左右各一).
+    /// 显示区域的水平宽度, 不是实际字符占据的宽度, 还要考虑 [`Packing`] 中的边距.
     display_width: usize,
-    /// 显示的内容.
-    content: String,
-    content_packing: Packing,
+    /// 显示的各段内容.
+    segments: Vec<Segment>,
+    /// 提示消息和它的显示样式, 显示在居中的内容之后.
+    message: Option<(String, Style)>,
+    /// 整个状态栏的显示样式.
+    style: Style,
     need_printing: bool,
 }
 
@@ -60,14 +96,72 @@ impl StatusBar {
             display_line: 0,
             display_start: 0,
             display_width: 0,
-            content: String::new(),
-            content_packing: Packing::Center,
+            segments: Vec::new(),
+            message: None,
+            style: Style::default(),
             need_printing: false,
         }
     }
 
-    pub fn set_packing(&mut self, packing: Packing) {
-        self.content_packing = packing;
+    pub fn set_style(&mut self, style: Style) {
+        if self.style != style {
+            self.set_need_printing();
+        }
+        self.style = style;
+    }
+
+    /// 设置显示的各段内容, 内容有变化时才需要重新打印.
+    pub fn set_segments(&mut self, segments: Vec<Segment>) {
+        if self.segments != segments {
+            self.set_need_printing();
+        }
+        self.segments = segments;
+    }
+
+    /// 设置提示消息及其显示样式, 比如错误信息, 为 None 时不显示消息.
+    pub fn set_message(&mut self, message: Option<(String, Style)>) {
+        if self.message != message {
+            self.set_need_printing();
+        }
+        self.message = message;
+    }
+
+    /// 按照各段内容的 [`Packing`] 排列出显示区域中每一列的字符和样式, 宽字符占据的后续列的字符为 None.
+    fn layout(&self) -> Vec<(Option<char>, Style)> {
+        let width = self.display_width;
+        let mut cells = vec![(Some(' '), self.style); width];
+        let center = self.segments.iter()
+            .filter(|segment| segment.packing == Packing::Center)
+            .map(|segment| segment.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let message = self.message.as_ref().map_or("", |(text, _)| text.as_str());
+        let separator = if center.is_empty() || message.is_empty() { "" } else { " " };
+        let start = (width / 2).saturating_sub((center.width() + separator.width() + message.width()) / 2);
+        let message_start = start + center.width() + separator.width();
+        place(&mut cells, start, &format!("{center}{separator}"), self.style);
+
+        let mut start = 0;
+        for segment in self.segments.iter().filter(|segment| matches!(segment.packing, Packing::Left(..))) {
+            place(&mut cells, start, &segment.padded_text(), self.style);
+            start += segment.width();
+        }
+
+        let right: Vec<&Segment> = self.segments.iter()
+            .filter(|segment| matches!(segment.packing, Packing::Right(..)))
+            .collect();
+        let mut start = width.saturating_sub(right.iter().map(|segment| segment.width()).sum());
+        for segment in right {
+            place(&mut cells, start, &segment.padded_text(), self.style);
+            start += segment.width();
+        }
+
+        if let Some((text, style)) = &self.message {
+            // 消息只显示一段时间, 所以优先于其他内容显示, 放不下时整体左移.
+            let message_start = message_start.min(width.saturating_sub(text.width()));
+            place(&mut cells, message_start, text, *style);
+        }
+        cells
     }
 
     /// 将自身内容打印到终端.
@@ -77,50 +171,41 @@ impl StatusBar {
     /// 此方法成功被调用之后无法让 cursor 回归原来位置, 需要手动调整.
     pub fn print_to(&self, terminal: &mut Terminal) -> error::Result<()> {
         terminal.hide_cursor()?;
-        // 清空显示区域.
-        terminal.move_cursor_to(Location::new(self.display_line, self.display_start))?;
-        terminal.print(" ".repeat(self.display_width))?;
-        // 确定处理 padding 过后的显示区域.
-        let (display_width, display_start) = match self.content_packing {
-            Packing::Center => {
-                // 这里暂时使用 len() 而不是 chars count, 防止对字符串的非字符边界索引.
-                let line_display_width = self.content.len().min(self.display_width);
-                (line_display_width,
-                 self.display_start + (self.display_width / 2 - line_display_width / 2))
-            }
-            Packing::Left(l_padding, r_padding) => {
-                if self.display_width > l_padding + r_padding {
-                    (self.display_width - l_padding - r_padding, self.display_start + l_padding)
-                } else {
-                    (self.display_width, self.display_start)
-                }
-            }
-            Packing::Right(l_padding, r_padding) => {
-                if self.display_width > l_padding + r_padding {
-                    let display_width = self.display_width - l_padding - r_padding;
-                    let line_display_width = self.content.len().min(display_width);
-                    (display_width,
-                     self.display_start + self.display_width - r_padding - line_display_width)
-                } else {
-                    let line_display_width = self.content.len().min(self.display_width);
-                    (self.display_width, self.display_start + self.display_width - line_display_width)
-                }
-            }
-        };
-
-        // 打印内容.
-        let line = &self.content[..display_width.min(self.content.len())];
-        terminal.move_cursor_to(Location::new(display_start, self.display_line))?;
-        terminal.print(line)?;
+        terminal.move_cursor_to(Location::new(self.display_start, self.display_line))?;
+        let cells = self.layout();
+        // 连续的相同样式的列一起打印.
+        for run in cells.chunk_by(|(_, a), (_, b)| a == b) {
+            let text: String = run.iter().filter_map(|(c, _)| *c).collect();
+            terminal.print_styled(text, run[0].1)?;
+        }
         terminal.show_cursor()?;
         Ok(())
     }
 
-    pub fn set_content(&mut self, s: String) {
-        if self.content != s {
-            self.set_need_printing();
+    /// 在状态栏中显示输入提示, 然后把 cursor 移动到提示的输入光标处.
+    ///
+    /// 如果输入内容超出了显示宽度, 那么只显示输入光标附近的内容.
+    pub fn print_prompt_to(&self, terminal: &mut Terminal, prompt: &Prompt) -> error::Result<()> {
+        terminal.hide_cursor()?;
+        terminal.move_cursor_to(Location::new(self.display_start, self.display_line))?;
+        terminal.print_styled(" ".repeat(self.display_width), self.style)?;
+        let cursor_column = prompt.label().width() + prompt.cursor_width();
+        // 需要从左侧隐藏的宽度, 保证 cursor 在显示区域内.
+        let hidden_width = (cursor_column + 1).saturating_sub(self.display_width);
+        let mut column = 0;
+        let mut visible = String::new();
+        for ch in prompt.label().chars().chain(prompt.input().chars()) {
+            let width = ch.width().unwrap_or(0);
+            if column >= hidden_width && column + width - hidden_width <= self.display_width {
+                visible.push(ch);
+            }
+            column += width;
         }
-        self.content = s;
+        terminal.move_cursor_to(Location::new(self.display_start, self.display_line))?;
+        terminal.print_styled(visible, self.style)?;
+        terminal.move_cursor_to(Location::new(self.display_start + cursor_column - hidden_width, self.display_line))?;
+        terminal.show_cursor()?;
+        Ok(())
     }
 
     /// 配置显示区域.
@@ -139,12 +224,83 @@ impl StatusBar {
     }
 }
 
+/// 从第 column 列开始以 style 样式放置文本, 超出显示区域的部分被截断, 被覆盖了一部分的宽字符替换为空格.
+fn place(cells: &mut [(Option<char>, Style)], mut column: usize, text: &str, style: Style) {
+    for c in text.chars() {
+        let width = c.width().unwrap_or(0);
+        if width == 0 {
+            continue;
+        }
+        if column + width > cells.len() {
+            break;
+        }
+        if cells[column].0.is_none() {
+            // 覆盖了宽字符的后半部分.
+            let head = cells[..column].iter().rposition(|(c, _)| c.is_some()).unwrap();
+            cells[head..column].iter_mut().for_each(|(c, _)| *c = Some(' '));
+        }
+        let mut tail = column + width;
+        while cells.get(tail).is_some_and(|(c, _)| c.is_none()) {
+            // 覆盖了宽字符的前半部分.
+            cells[tail].0 = Some(' ');
+            tail += 1;
+        }
+        cells[column] = (Some(c), style);
+        cells[column + 1..column + width].fill((None, style));
+        column += width;
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::editor::editarea::Area;
+    use crate::editor::statusbar::{Packing, Segment, StatusBar};
+    use crate::editor::terminal::Style;
+
+    fn render(segments: Vec<Segment>, message: &str, width: usize) -> String {
+        let mut status_bar = StatusBar::new();
+        status_bar.configure_area(Area::new(0, 0, width, 1));
+        status_bar.set_segments(segments);
+        if !message.is_empty() {
+            status_bar.set_message(Some((message.to_owned(), Style { reverse: true, ..Style::default() })));
+        }
+        status_bar.layout().into_iter().filter_map(|(c, _)| c).collect()
+    }
+
     #[test]
     fn packing() {
-        // test center
-        // test left
-        // test right
+        let segments = vec![
+            Segment::new("a.rs", Packing::Left(1, 1)),
+            Segment::new("[+]", Packing::Left(0, 1)),
+            Segment::new("mid", Packing::Center),
+            Segment::new("1:1", Packing::Right(1, 1)),
+            Segment::new("LF", Packing::Right(1, 1)),
+        ];
+        assert_eq!(render(segments.clone(), "", 30), " a.rs [+]     mid     1:1  LF ");
+        // 提示消息显示在居中的内容之后.
+        assert_eq!(render(segments.clone(), "hi", 30), " a.rs [+]   mid hi    1:1  LF ");
+        // 宽度不足时靠右的内容优先, 然后是靠左的内容, 边距也会覆盖其他内容.
+        assert_eq!(render(segments.clone(), "", 20), " a.rs [+] i 1:1  LF ");
+        assert_eq!(render(segments.clone(), "", 12), " a. 1:1  LF ");
+        assert_eq!(render(segments, "", 8), " 1:1  LF");
+        assert_eq!(render(vec![Segment::new("x", Packing::Right(0, 2))], "", 5), "  x  ");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn wide_chars() {
+        let segments = vec![
+            Segment::new("文件", Packing::Left(0, 0)),
+            Segment::new("中文", Packing::Right(0, 0)),
+        ];
+        assert_eq!(render(segments.clone(), "", 8), "文件中文");
+        // 靠右的内容覆盖了靠左的宽字符的后半部分.
+        assert_eq!(render(segments.clone(), "", 7), "文 中文");
+        let segments = vec![
+            Segment::new("abc", Packing::Left(0, 0)),
+            Segment::new("中文", Packing::Right(0, 0)),
+        ];
+        assert_eq!(render(segments.clone(), "", 5), "a中文");
+        // 超出显示区域的宽字符被截断.
+        assert_eq!(render(segments, "", 3), "中c");
+    }
+}
@@ -1,14 +1,17 @@
 use std::cmp;
 use std::ops::Add;
+use std::time::Duration;
 use std::fmt::Display;
 use crossterm::terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, enable_raw_mode, disable_raw_mode};
 use crossterm::cursor::{Hide, Show};
-use crossterm::event;
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event};
 use crossterm::{Command, queue};
 use std::io;
 use std::io::Write;
 use crossterm::cursor::MoveTo;
-use crossterm::style::Print;
+use crossterm::style::{Attribute, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor};
+
+pub use crossterm::style::Color;
 
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
@@ -17,6 +20,19 @@ pub struct Location {
     pub y: usize,
 }
 
+/// 按照先行 (y) 后列 (x) 的顺序比较, 即 [`Location`] 在文本中的前后顺序.
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Location {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (self.y, self.x).cmp(&(other.y, other.x))
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
 pub struct Size {
     pub width: usize,
@@ -37,78 +53,216 @@ impl PartialOrd for Size {
     }
 }
 
-pub struct Terminal {
-    _ign: u8,
+/// 文字的显示样式, 颜色为 None 时使用终端的默认颜色.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    /// 交换前景色和背景色.
+    pub reverse: bool,
 }
 
-impl Terminal {
-    fn queue_command(&mut self, com: impl Command) -> io::Result<()> {
-        queue!(io::stdout(), com)
+impl Style {
+    pub const fn new(fg: Option<Color>, bg: Option<Color>) -> Style {
+        Style { fg, bg, reverse: false }
     }
+}
 
-    fn enter_alternate_screen(&mut self) -> io::Result<()> {
-        self.queue_command(EnterAlternateScreen)
-    }
+/// 编辑器的绘制目标, 比如真实的终端 ([`CrosstermTerminal`]) 或者内存中的虚拟终端
+/// ([`VirtualTerminal`](crate::editor::virtualterminal::VirtualTerminal)).
+///
+/// 打印的内容从 cursor 处开始, 打印之后 cursor 移动到内容之后, 超出行末的内容不会折到下一行.
+pub trait RenderTarget {
+    /// 开始绘制之前调用, 比如进入 raw mode.
+    fn initialize(&mut self) -> io::Result<()>;
+    /// 结束绘制之后调用, 恢复 [`RenderTarget::initialize`] 之前的状态.
+    fn destruct(&mut self) -> io::Result<()>;
+    fn clear_screen(&mut self) -> io::Result<()>;
+    /// 使用指定的样式打印, 打印之后恢复默认样式.
+    fn print_styled(&mut self, s: &str, style: Style) -> io::Result<()>;
+    fn hide_cursor(&mut self) -> io::Result<()>;
+    fn show_cursor(&mut self) -> io::Result<()>;
+    fn move_cursor_to(&mut self, loc: Location) -> io::Result<()>;
+    /// 让之前的绘制操作生效.
+    fn flush(&mut self) -> io::Result<()>;
+    fn size(&self) -> io::Result<Size>;
+}
 
-    fn exit_alternate_screen(&mut self) -> io::Result<()> {
-        self.queue_command(LeaveAlternateScreen)
-    }
+/// 编辑器的事件来源, 比如真实终端中的按键或者测试中预先准备好的按键.
+pub trait EventSource {
+    /// 读取一个事件, 没有事件时阻塞直到有事件到达.
+    fn read_event(&mut self) -> io::Result<Event>;
+    /// 等待事件, 最多等待 timeout, 返回是否有可以读取的事件.
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<bool>;
+}
 
-    pub fn new() -> Terminal {
-        Terminal { _ign: 0 }
-    }
+/// 使用 crossterm 操作的真实终端, 绘制到 stdout, 事件来自终端的输入.
+#[derive(Debug, Default)]
+pub struct CrosstermTerminal;
 
-    pub fn flush(&mut self) -> io::Result<()> {
-        io::stdout().flush()
+impl CrosstermTerminal {
+    fn queue_command(&mut self, com: impl Command) -> io::Result<()> {
+        queue!(io::stdout(), com)
     }
+}
 
-    pub fn initialize(&mut self) -> io::Result<()> {
-        self.enter_alternate_screen()?;
+impl RenderTarget for CrosstermTerminal {
+    /// 进入 raw mode 和 alternate screen, 并开启鼠标事件的捕获.
+    ///
+    /// 先进入 raw mode, 以免无法使用终端时 alternate screen 的控制序列残留在输出中.
+    fn initialize(&mut self) -> io::Result<()> {
         enable_raw_mode()?;
+        self.queue_command(EnterAlternateScreen)?;
+        self.queue_command(EnableMouseCapture)?;
         Ok(())
     }
 
-    pub fn destruct(&mut self) -> io::Result<()> {
+    fn destruct(&mut self) -> io::Result<()> {
+        self.queue_command(DisableMouseCapture)?;
         disable_raw_mode()?;
-        self.exit_alternate_screen()?;
+        self.queue_command(LeaveAlternateScreen)?;
         self.flush()?; // 这样才能让 exit_alternate_screen 立即生效, 不然的话可能导致报错输出在 alternate_screen 中.
         Ok(())
     }
 
-    pub fn clear_screen(&mut self) -> io::Result<()> {
+    fn clear_screen(&mut self) -> io::Result<()> {
         self.queue_command(Clear(ClearType::All))
     }
 
-    pub fn print(&mut self, s: impl Display) -> io::Result<()> {
-        self.queue_command(Print(s))
+    fn print_styled(&mut self, s: &str, style: Style) -> io::Result<()> {
+        if style == Style::default() {
+            return self.queue_command(Print(s));
+        }
+        if let Some(fg) = style.fg {
+            self.queue_command(SetForegroundColor(fg))?;
+        }
+        if let Some(bg) = style.bg {
+            self.queue_command(SetBackgroundColor(bg))?;
+        }
+        if style.reverse {
+            self.queue_command(SetAttribute(Attribute::Reverse))?;
+        }
+        self.queue_command(Print(s))?;
+        self.queue_command(SetAttribute(Attribute::Reset))?;
+        self.queue_command(ResetColor)
     }
 
-    pub fn hide_cursor(&mut self) -> io::Result<()> {
+    fn hide_cursor(&mut self) -> io::Result<()> {
         self.queue_command(Hide)
     }
 
-    pub fn show_cursor(&mut self) -> io::Result<()> {
+    fn show_cursor(&mut self) -> io::Result<()> {
         self.queue_command(Show)
     }
 
-    pub fn move_cursor_to(&mut self, loc: Location) -> io::Result<()> {
+    fn move_cursor_to(&mut self, loc: Location) -> io::Result<()> {
         let loc = loc.as_u16_checked().ok_or_else(
             || io::Error::new(io::ErrorKind::InvalidInput, "location cannot be cast to (u16, u16)")
         )?;
         self.queue_command(MoveTo(loc.0, loc.1))
     }
 
-    /// 读取终端事件.
-    ///
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        let size = crossterm::terminal::size()?;
+        Ok(size.into())
+    }
+}
+
+impl EventSource for CrosstermTerminal {
     /// 见 `crossterm::event::read` 函数.
-    pub fn read_event_blocking(&self) -> io::Result<event::Event> {
+    ///
+    /// stdin 不是终端时 (比如通过管道输入了要编辑的文本), crossterm 会从 `/dev/tty` 读取事件,
+    /// 所以 stdin 被读取完之后仍然可以通过键盘进行编辑.
+    fn read_event(&mut self) -> io::Result<Event> {
         event::read()
     }
 
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+}
+
+/// 编辑器使用的终端, 把绘制交给 [`RenderTarget`], 从 [`EventSource`] 读取事件.
+///
+/// 默认使用真实终端 ([`CrosstermTerminal`]).
+pub struct Terminal {
+    target: Box<dyn RenderTarget>,
+    events: Box<dyn EventSource>,
+}
+
+impl Default for Terminal {
+    fn default() -> Terminal {
+        Terminal::with(CrosstermTerminal, CrosstermTerminal)
+    }
+}
+
+impl Terminal {
+    /// 使用真实终端 ([`CrosstermTerminal`]).
+    pub fn new() -> Terminal {
+        Terminal::default()
+    }
+
+    /// 使用指定的绘制目标和事件来源, 比如在测试中使用
+    /// [`VirtualTerminal`](crate::editor::virtualterminal::VirtualTerminal).
+    pub fn with(target: impl RenderTarget + 'static, events: impl EventSource + 'static) -> Terminal {
+        Terminal { target: Box::new(target), events: Box::new(events) }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.target.flush()
+    }
+
+    /// 见 [`RenderTarget::initialize`].
+    pub fn initialize(&mut self) -> io::Result<()> {
+        self.target.initialize()
+    }
+
+    pub fn destruct(&mut self) -> io::Result<()> {
+        self.target.destruct()
+    }
+
+    pub fn clear_screen(&mut self) -> io::Result<()> {
+        self.target.clear_screen()
+    }
+
+    pub fn print(&mut self, s: impl Display) -> io::Result<()> {
+        self.print_styled(s, Style::default())
+    }
+
+    /// 使用指定的样式打印, 打印之后恢复默认样式.
+    pub fn print_styled(&mut self, s: impl Display, style: Style) -> io::Result<()> {
+        self.target.print_styled(&s.to_string(), style)
+    }
+
+    pub fn hide_cursor(&mut self) -> io::Result<()> {
+        self.target.hide_cursor()
+    }
+
+    pub fn show_cursor(&mut self) -> io::Result<()> {
+        self.target.show_cursor()
+    }
+
+    pub fn move_cursor_to(&mut self, loc: Location) -> io::Result<()> {
+        self.target.move_cursor_to(loc)
+    }
+
+    /// 读取事件, 没有事件时阻塞, 见 [`EventSource::read_event`].
+    pub fn read_event_blocking(&mut self) -> io::Result<Event> {
+        self.events.read_event()
+    }
+
+    /// 等待事件, 最多等待 timeout, 返回是否有可以读取的事件.
+    pub fn poll_event(&mut self, timeout: Duration) -> io::Result<bool> {
+        self.events.poll_event(timeout)
+    }
+
     /// 获取终端尺寸.
     pub fn size(&self) -> io::Result<Size> {
-        let size = crossterm::terminal::size()?;
-        Ok(size.into())
+        self.target.size()
     }
 }
 
@@ -136,23 +290,23 @@ macro_rules! usize_pair {
             }
         }
 
-        // impl TryFrom<$t> for (u16, u16) { // TryInto 和 TryFrom 会自动实现, 因为有了 Into 特征.
+        // impl TryFrom<$t> for (u16, u16) { // TryInto 和 TryFrom 会自动实现, 因为有了 From 特征.
 
-        impl Into<(u16, u16)> for $t {
-            fn into(self) -> (u16, u16) {
-                self.as_u16()
+        impl From<$t> for (u16, u16) {
+            fn from(value: $t) -> (u16, u16) {
+                value.as_u16()
             }
         }
-        
-        impl Into<$t> for (u16, u16) {
-            fn into(self) -> $t {
-                $t::new(self.0 as usize, self.1 as usize)
+
+        impl From<(u16, u16)> for $t {
+            fn from(value: (u16, u16)) -> $t {
+                $t::new(value.0 as usize, value.1 as usize)
             }
         }
 
-        impl Into<(usize, usize)> for $t {
-            fn into(self) -> (usize, usize) {
-                (self.$u1, self.$u2)
+        impl From<$t> for (usize, usize) {
+            fn from(value: $t) -> (usize, usize) {
+                (value.$u1, value.$u2)
             }
         }
 
@@ -0,0 +1,231 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io;
+use std::rc::Rc;
+use std::time::Duration;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthChar;
+use crate::editor::terminal::{EventSource, Location, RenderTarget, Size, Style};
+
+/// 虚拟终端屏幕中的一格.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Cell {
+    /// 这一格显示的字符, 宽字符占据的后续格为 None.
+    pub ch: Option<char>,
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Cell {
+        Cell { ch: Some(' '), style: Style::default() }
+    }
+}
+
+#[derive(Debug)]
+struct Screen {
+    size: Size,
+    /// 按行排列的格子, 大小总是和 size 一致.
+    cells: Vec<Vec<Cell>>,
+    cursor: Location,
+    cursor_visible: bool,
+    events: VecDeque<Event>,
+}
+
+impl Screen {
+    /// 在 cursor 处打印一个字符, 超出行末时忽略, 被覆盖了一部分的宽字符替换为空格.
+    fn put(&mut self, c: char, style: Style) {
+        let width = c.width().unwrap_or(0);
+        let Location { x, y } = self.cursor;
+        let Some(row) = self.cells.get_mut(y) else {
+            return;
+        };
+        if width == 0 || x + width > row.len() {
+            self.cursor.x += width;
+            return;
+        }
+        if row[x].ch.is_none() {
+            // 覆盖了宽字符的后半部分.
+            let head = row[..x].iter().rposition(|cell| cell.ch.is_some()).unwrap();
+            row[head..x].iter_mut().for_each(|cell| cell.ch = Some(' '));
+        }
+        let mut tail = x + width;
+        while row.get(tail).is_some_and(|cell| cell.ch.is_none()) {
+            // 覆盖了宽字符的前半部分.
+            row[tail].ch = Some(' ');
+            tail += 1;
+        }
+        row[x] = Cell { ch: Some(c), style };
+        row[x + 1..x + width].fill(Cell { ch: None, style });
+        self.cursor.x += width;
+    }
+}
+
+/// 内存中的虚拟终端, 同时是 [`RenderTarget`] 和 [`EventSource`], 用于在测试中或者嵌入其他界面时驱动编辑器.
+///
+/// 克隆得到的虚拟终端共享同一个屏幕和事件队列, 所以可以把一个交给编辑器,
+/// 用另一个输入事件和检查屏幕的内容.
+#[derive(Debug, Clone)]
+pub struct VirtualTerminal {
+    screen: Rc<RefCell<Screen>>,
+}
+
+impl VirtualTerminal {
+    pub fn new(size: Size) -> VirtualTerminal {
+        VirtualTerminal {
+            screen: Rc::new(RefCell::new(Screen {
+                size,
+                cells: vec![vec![Cell::default(); size.width]; size.height],
+                cursor: Location::default(),
+                cursor_visible: true,
+                events: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// 把事件加入事件队列的末尾.
+    pub fn push_event(&self, event: Event) {
+        self.screen.borrow_mut().events.push_back(event);
+    }
+
+    /// 加入按键事件.
+    pub fn push_key(&self, code: KeyCode, modifiers: KeyModifiers) {
+        self.push_event(Event::Key(KeyEvent::new(code, modifiers)));
+    }
+
+    /// 依次加入 s 中每个字符的按键事件, `\n` 为 Enter, `\t` 为 Tab.
+    pub fn type_str(&self, s: &str) {
+        for c in s.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\t' => KeyCode::Tab,
+                c => KeyCode::Char(c),
+            };
+            self.push_key(code, KeyModifiers::NONE);
+        }
+    }
+
+    /// 改变屏幕尺寸, 并加入对应的 [`Event::Resize`] 事件, 屏幕的内容被清空.
+    pub fn resize(&self, size: Size) {
+        let mut screen = self.screen.borrow_mut();
+        screen.size = size;
+        screen.cells = vec![vec![Cell::default(); size.width]; size.height];
+        screen.events.push_back(Event::Resize(size.width as u16, size.height as u16));
+    }
+
+    /// 还没有被读取的事件数量.
+    pub fn pending_events(&self) -> usize {
+        self.screen.borrow().events.len()
+    }
+
+    /// 第 y 行的格子, y 超出屏幕时为 None.
+    pub fn row(&self, y: usize) -> Option<Vec<Cell>> {
+        self.screen.borrow().cells.get(y).cloned()
+    }
+
+    /// 第 y 行显示的文本, 去除了行末的空格, y 超出屏幕时为空字符串.
+    pub fn line(&self, y: usize) -> String {
+        let line: String = self.row(y).unwrap_or_default().iter().filter_map(|cell| cell.ch).collect();
+        line.trim_end().to_owned()
+    }
+
+    /// 屏幕中每一行显示的文本, 见 [`VirtualTerminal::line`].
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.screen.borrow().size.height).map(|y| self.line(y)).collect()
+    }
+
+    /// cursor 在屏幕中的位置, cursor 被隐藏时为 None.
+    pub fn cursor(&self) -> Option<Location> {
+        let screen = self.screen.borrow();
+        screen.cursor_visible.then_some(screen.cursor)
+    }
+}
+
+impl RenderTarget for VirtualTerminal {
+    fn initialize(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn destruct(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn clear_screen(&mut self) -> io::Result<()> {
+        let mut screen = self.screen.borrow_mut();
+        screen.cells.iter_mut().for_each(|row| row.fill(Cell::default()));
+        Ok(())
+    }
+
+    fn print_styled(&mut self, s: &str, style: Style) -> io::Result<()> {
+        let mut screen = self.screen.borrow_mut();
+        s.chars().for_each(|c| screen.put(c, style));
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.screen.borrow_mut().cursor_visible = false;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.screen.borrow_mut().cursor_visible = true;
+        Ok(())
+    }
+
+    fn move_cursor_to(&mut self, loc: Location) -> io::Result<()> {
+        self.screen.borrow_mut().cursor = loc;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        Ok(self.screen.borrow().size)
+    }
+}
+
+impl EventSource for VirtualTerminal {
+    /// 取出事件队列中的第一个事件.
+    ///
+    /// # Errors
+    ///
+    /// - [`io::ErrorKind::UnexpectedEof`]: 事件队列为空, 虚拟终端不会有新的事件自己到达.
+    fn read_event(&mut self) -> io::Result<Event> {
+        self.screen.borrow_mut().events.pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "no more events in the virtual terminal"))
+    }
+
+    /// 返回事件队列是否不为空, 不会等待.
+    fn poll_event(&mut self, _timeout: Duration) -> io::Result<bool> {
+        Ok(!self.screen.borrow().events.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::terminal::{Color, Location, RenderTarget, Size, Style};
+    use crate::editor::virtualterminal::VirtualTerminal;
+
+    #[test]
+    fn print_and_overwrite() {
+        let mut terminal = VirtualTerminal::new(Size::new(8, 2));
+        terminal.print_styled("abcdefghij", Style::default()).unwrap();
+        assert_eq!(terminal.line(0), "abcdefgh");
+        assert_eq!(terminal.line(1), "");
+        terminal.move_cursor_to(Location::new(1, 1)).unwrap();
+        terminal.print_styled("中文", Style::new(Some(Color::Red), None)).unwrap();
+        assert_eq!(terminal.line(1), " 中文");
+        assert_eq!(terminal.row(1).unwrap()[2].ch, None);
+        assert_eq!(terminal.row(1).unwrap()[3].style.fg, Some(Color::Red));
+        // 覆盖宽字符的一半.
+        terminal.move_cursor_to(Location::new(2, 1)).unwrap();
+        terminal.print_styled("x", Style::default()).unwrap();
+        assert_eq!(terminal.line(1), "  x文");
+        assert_eq!(terminal.cursor(), Some(Location::new(3, 1)));
+        terminal.hide_cursor().unwrap();
+        assert_eq!(terminal.cursor(), None);
+        terminal.clear_screen().unwrap();
+        assert_eq!(terminal.lines(), ["", ""]);
+    }
+}
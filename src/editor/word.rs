@@ -0,0 +1,119 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// 字符的类别, 单词移动时相邻的同类字符被视为同一个单词.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum CharClass {
+    /// 空白字符, 不属于任何单词.
+    Whitespace,
+    /// 标识符中的字符, 即字母, 数字和下划线.
+    Word,
+    /// 中日韩文字, 这些文字之间通常没有空格.
+    Cjk,
+    /// 其他字符, 比如标点和运算符.
+    Punctuation,
+}
+
+impl CharClass {
+    fn of(c: char) -> CharClass {
+        if c.is_whitespace() {
+            CharClass::Whitespace
+        } else if is_cjk(c) {
+            CharClass::Cjk
+        } else if c.is_alphanumeric() || c == '_' {
+            CharClass::Word
+        } else {
+            CharClass::Punctuation
+        }
+    }
+}
+
+/// 是否为中日韩文字, 包括汉字, 假名, 注音和谚文, 不包括全角的标点.
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x11FF | 0x2E80..=0x2FDF | 0x3040..=0x31FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF
+            | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0x20000..=0x3FFFF
+    )
+}
+
+/// 行中每个单词的开始位置 (字节索引), 按照从前往后的顺序排列, 空白不属于任何单词.
+///
+/// 行先按照 Unicode 的单词分割规则 (UAX #29) 分割, 然后在相邻字素簇 (grapheme cluster)
+/// 的字符类别不同处继续分割, 比如 `foo.bar(baz)` 分为 `foo`, `.`, `bar`, `(`, `baz` 和 `)`,
+/// 相邻的同类的标点或者中日韩文字合并为一个单词.
+///
+/// `subword` 为 true 时, 标识符还会在驼峰命名 (camelCase) 和下划线命名 (snake_case) 的分界处分割,
+/// 比如 `parseHTTPRequest_v2` 分为 `parse`, `HTTP`, `Request_` 和 `v2`.
+pub fn word_starts(line: &str, subword: bool) -> Vec<usize> {
+    let graphemes: Vec<(usize, bool, char, CharClass)> = line.split_word_bound_indices()
+        .flat_map(|(start, segment)| {
+            segment.grapheme_indices(true).map(move |(idx, grapheme)| {
+                let c = grapheme.chars().next().unwrap(); // 字素簇不为空.
+                (start + idx, idx == 0, c, CharClass::of(c))
+            })
+        })
+        .collect();
+    let mut starts = Vec::new();
+    for (i, &(idx, segment_start, c, class)) in graphemes.iter().enumerate() {
+        if class == CharClass::Whitespace {
+            continue;
+        }
+        let is_start = match i.checked_sub(1).map(|i| graphemes[i]) {
+            None => true,
+            Some((_, _, prev, prev_class)) => {
+                let next = graphemes.get(i + 1).map(|(_, _, next, _)| *next);
+                prev_class != class
+                    || (class == CharClass::Word && segment_start)
+                    || (subword && class == CharClass::Word && is_subword_boundary(prev, c, next))
+            }
+        };
+        if is_start {
+            starts.push(idx);
+        }
+    }
+    starts
+}
+
+/// 标识符中 prev 和 c 之间是否为子单词的分界, next 为 c 之后的字符.
+fn is_subword_boundary(prev: char, c: char, next: Option<char>) -> bool {
+    if c == '_' {
+        return false;
+    }
+    prev == '_'
+        || (c.is_uppercase() && (prev.is_lowercase() || prev.is_numeric()))
+        // 连续的大写字母之后是小写字母时, 最后一个大写字母属于下一个单词, 比如 `HTTPRequest`.
+        || (c.is_uppercase() && prev.is_uppercase() && next.is_some_and(char::is_lowercase))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::word::word_starts;
+
+    fn words(line: &str, subword: bool) -> Vec<&str> {
+        let starts = word_starts(line, subword);
+        starts.iter()
+            .map(|start| {
+                let rest = &line[*start..];
+                let end = starts.iter().find(|s| *s > start).map_or(rest.len(), |s| s - start);
+                rest[..end].trim_end()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn words_by_class() {
+        assert_eq!(words("foo.bar(baz)", false), ["foo", ".", "bar", "(", "baz", ")"]);
+        assert_eq!(words("  let x = a->b;", false), ["let", "x", "=", "a", "->", "b", ";"]);
+        assert_eq!(words("我喜欢Rust编程，真的", false), ["我喜欢", "Rust", "编程", "，", "真的"]);
+        assert_eq!(words("e\u{301}te 3.14", false), ["e\u{301}te", "3", ".", "14"]);
+        assert_eq!(words("", false), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn subwords() {
+        assert_eq!(words("parseHTTPRequest_v2", true), ["parse", "HTTP", "Request_", "v2"]);
+        assert_eq!(words("snake_case_name", true), ["snake_", "case_", "name"]);
+        assert_eq!(words("snake_case_name", false), ["snake_case_name"]);
+        assert_eq!(words("__init__", true), ["__", "init__"]);
+    }
+}
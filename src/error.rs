@@ -1,10 +1,13 @@
-use crate::editor::Size;
+use crate::editor::{Location, Severity, Size};
 use std::io;
+use std::path::PathBuf;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("IOError occurred: {0:?}")]
+    #[error("IO error: {0}.")]
     IOError(#[from] io::Error),
+    #[error("Failed to open the terminal: {0}.")]
+    TerminalUnavailable(io::Error),
 
     // buffer.
     #[error("The print area size doesn't fit the buffer.")]
@@ -15,12 +18,64 @@ pub enum Error {
     CaretOutOfLen { caret: usize, len: usize },
     #[error("End of buffer reached.")]
     EndOfFile,
+    #[error("Beginning of buffer reached.")]
+    BeginningOfFile,
     #[error("Deleting char at the very beginning of the buffer.")]
     DelAtBeginning,
+    #[error("Invalid range, start {start:?} is after end {end:?}.")]
+    InvalidRange { start: Location, end: Location },
+    #[error("The file is not valid {encoding} text.")]
+    MalformedText { encoding: &'static str },
+    #[error("The text contains characters that can't be encoded in {encoding}.")]
+    UnencodableText { encoding: &'static str },
+    #[error("Unknown line ending `{0}`, expected `lf` or `crlf`.")]
+    UnknownLineEnding(String),
+    #[error("Unknown encoding `{0}`.")]
+    UnknownEncoding(String),
+    #[error("The buffer is read-only.")]
+    ReadOnly,
+
+    // keymap.
+    #[error("Invalid key chord `{0}`.")]
+    InvalidKeyChord(String),
+    #[error("Unknown command `{0}`.")]
+    UnknownCommand(String),
+    #[error("Key sequence `{0}` is bound more than once.")]
+    DuplicateBinding(String),
+    #[error("Key sequence `{keys}` is bound to a command, but it is also a prefix of `{longer}`.")]
+    ConflictingBinding { keys: String, longer: String },
+
+    // command.
+    #[error("Invalid argument `{argument}` for command `{command}`.")]
+    InvalidArgument { command: String, argument: String },
+    #[error("Unknown option `{0}`.")]
+    UnknownOption(String),
+    #[error("Invalid value `{value}` for option `{option}`.")]
+    InvalidOptionValue { option: String, value: String },
+    #[error("Invalid goto target `{0}`, expected `line`, `line:column`, `+N`, `-N` or `N%`.")]
+    InvalidGotoTarget(String),
+
+    // config.
+    #[error("Failed to parse config: {0}")]
+    ConfigParse(#[from] toml::de::Error),
+    #[error("Error in config file {path:?}: {source}")]
+    ConfigFile { path: PathBuf, source: Box<Error> },
+    #[error("Unknown filetype `{0}`.")]
+    UnknownFiletype(String),
 
     // edit area.
     #[error("Buffer size {buffer_size:?} exceeds the display area size {area_size:?}.")]
     BufferSizeExceeds { buffer_size: Size, area_size: Size },
 }
 
+impl Error {
+    /// 错误作为消息显示时的严重程度, 只是没有完成操作的错误 (比如到达了 buffer 的开头) 为警告.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Error::EndOfFile | Error::BeginningOfFile | Error::DelAtBeginning | Error::ReadOnly => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
\ No newline at end of file
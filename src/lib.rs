@@ -1,7 +1,20 @@
+use std::env;
+use std::path::PathBuf;
+
+pub mod cli;
 pub mod editor;
 pub mod error;
 
-const CARGO_PKG_NAME: &'static str = env!("CARGO_PKG_NAME");
+const CARGO_PKG_NAME: &str = env!("CARGO_PKG_NAME");
+
+/// 配置文件所在的目录, 即 `$XDG_CONFIG_HOME/vegetor`, 没有设置 `XDG_CONFIG_HOME` 时为 `~/.config/vegetor`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join(CARGO_PKG_NAME))
+}
 
 trait CharsCount {
     fn chars_count(&self) -> usize;
@@ -1,21 +1,62 @@
-use std::path;
 use std::path::Path;
+use std::io::{self, IsTerminal};
+use std::process;
+use clap::{CommandFactory, Parser};
+use vegetor::cli::{self, Cli};
 use vegetor::editor::{Editor, EditorBuildConfig, BufferLoadConfig};
 
 // 如果这里使用 fn main() -> anyhow::Result<()> { ... } 的话,
 // 如果产生了错误, 那么 editor 的 panic_handler 将无法捕获错误,
 // 应该在 main 函数中使用 unwrap 直接 panic.
 fn main() {
-    let mut config = EditorBuildConfig::default();
-    let args: Vec<String> = std::env::args().collect();
-    match args.get(1) {
+    let cli = Cli::parse();
+    let (file, caret_position) = cli.file_and_position().unwrap_or_else(|e| {
+        Cli::command().error(clap::error::ErrorKind::TooManyValues, e).exit()
+    });
+    let mut config = EditorBuildConfig {
+        caret_position,
+        readonly: cli.readonly,
+        encoding: cli.encoding,
+        line_ending: cli.line_ending,
+        ..Default::default()
+    };
+    config.output_path = cli.output.as_deref();
+    match file {
+        Some("-") => {
+            config.edit_text_config = BufferLoadConfig::Stdin;
+        }
         Some(file) => {
             config.edit_text_config = BufferLoadConfig::File(Path::new(file));
         }
-        None => {
-            config.welcome_config = BufferLoadConfig::File(Path::new("welcome.txt"));
+        // 通过管道或者重定向输入了文本, 比如 `vegetor -o out.txt < in.txt`.
+        None if !io::stdin().is_terminal() => {
+            config.edit_text_config = BufferLoadConfig::Stdin;
+        }
+        None if !cli.no_welcome => {
+            config.welcome_config = BufferLoadConfig::String(cli::WELCOME_TEXT);
         }
+        None => {}
+    }
+    let config_dir = cli.config_dir();
+    let keys_file = config_dir.as_ref().map(|dir| dir.join("keys.toml"));
+    if let Some(keys_file) = keys_file.as_deref().filter(|file| file.exists()) {
+        config.keymap_config = Some(keys_file);
     }
-    let mut editor = Editor::build(&config).unwrap();
+    let settings_file = config_dir.as_ref().map(|dir| dir.join("config.toml"));
+    if let Some(settings_file) = settings_file.as_deref().filter(|file| file.exists()) {
+        config.settings_config = Some(settings_file);
+    }
+    let mut editor = Editor::build(&config).unwrap_or_else(|e| {
+        // 配置和文本的加载错误在接管终端之前产生, 可以直接输出.
+        exit_with_error(e)
+    });
     editor.run().unwrap();
-}
\ No newline at end of file
+    // 恢复终端之后再输出, 嵌入其他界面或者在虚拟终端中运行的编辑器不会输出.
+    drop(editor);
+    println!("{} leaving...", env!("CARGO_PKG_NAME"));
+}
+
+fn exit_with_error(e: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {e}", env!("CARGO_PKG_NAME"));
+    process::exit(1);
+}